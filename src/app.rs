@@ -1,18 +1,57 @@
-use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
-use std::io::BufReader;
+use crate::batch_rename::BatchRenameDialog;
+use crate::bookmarks::Bookmarks;
+use crate::file_ops::{self, LinkKind, Measure};
+use crate::frecency::{self, Frecency, Jump};
+use crate::goto::GoTo;
+use crate::jobs::{JobQueue, Operation, Recorder, Transfer};
+use crate::journal::{Change, Journal, Step};
+use crate::panel::{Panel, Tab};
+use crate::permissions::PermissionsDialog;
+use crate::properties;
+use crate::rename;
+use crate::search::{ContentSearch, RecursiveSearch};
+use crate::sort::SortKey;
+use crate::trash::{self, TrashEntry};
+use crate::ui::format_size;
+use crate::view::View;
+use crate::visibility::Visibility;
+use globset::Glob;
+use regex::Regex;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 use std::{
     fs::{self, File},
-    io::{self, BufRead},
+    io,
 };
 
+// Сколько строк показывать над найденной строкой в превью
+const CONTEXT_LINES: usize = 2;
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Mode {
     Normal,
     Menu,
     Search,
+    RecursiveSearch,
+    ContentSearch,
     Context,
+    Conflict,
+    Jobs,
+    Trash,
+    Confirm,
+    History,
+    Sort,
+    Columns,
+    Properties,
+    Permissions,
+    BatchRename,
+    SetMark,
+    JumpMark,
+    Bookmarks,
+    Jump,
+    GoTo,
 }
 
 pub enum InputMode {
@@ -20,24 +59,246 @@ pub enum InputMode {
     Normal,
 }
 
+#[derive(Clone, Copy, PartialEq)]
 pub enum MenuAction {
     CreateFile,
     CreateDir,
     Rename,
+    BulkRename,
+    PatternRename,
+    SelectGlob,
+    IgnorePatterns,
+    RenameBookmark,
+    ImportHistory,
+    Properties,
+    Permissions,
+    Link(LinkKind),
     Delete,
+    DeletePermanently,
+    Trash,
+    Copy,
+    Cut,
+    Paste,
+    CopyTo,
+    MoveTo,
     Cancel,
 }
 
-pub enum ContextAction {
-    Open,
+const MENU: [(MenuAction, &str); 20] = [
+    (MenuAction::Delete, "Удалить в корзину"),
+    (MenuAction::DeletePermanently, "Удалить навсегда"),
+    (MenuAction::CreateFile, "Создать файл"),
+    (MenuAction::CreateDir, "Создать папку"),
+    (MenuAction::Rename, "Переименовать"),
+    (MenuAction::BulkRename, "Переименовать списком"),
+    (MenuAction::PatternRename, "Переименовать по шаблону"),
+    (
+        MenuAction::Link(LinkKind::Absolute),
+        "Создать символическую ссылку",
+    ),
+    (
+        MenuAction::Link(LinkKind::Relative),
+        "Создать относительную ссылку",
+    ),
+    (MenuAction::Link(LinkKind::Hard), "Создать жёсткую ссылку"),
+    (MenuAction::Copy, "Копировать"),
+    (MenuAction::Cut, "Вырезать"),
+    (MenuAction::Paste, "Вставить"),
+    (MenuAction::CopyTo, "Копировать в другую панель"),
+    (MenuAction::MoveTo, "Переместить в другую панель"),
+    (MenuAction::Trash, "Корзина"),
+    (MenuAction::Properties, "Свойства"),
+    (MenuAction::Permissions, "Права и владелец"),
+    (
+        MenuAction::ImportHistory,
+        "Импортировать историю папок (zoxide, z, autojump)",
+    ),
+    (MenuAction::Cancel, "Отмена"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConflictChoice {
+    Overwrite,
+    Skip,
+    Rename,
+}
+
+// (выбор, применить ко всем); None — отменить вставку
+const CONFLICT_MENU: [(Option<(ConflictChoice, bool)>, &str); 7] = [
+    (Some((ConflictChoice::Overwrite, false)), "Перезаписать"),
+    (Some((ConflictChoice::Skip, false)), "Пропустить"),
+    (
+        Some((ConflictChoice::Rename, false)),
+        "Сохранить под другим именем",
+    ),
+    (Some((ConflictChoice::Overwrite, true)), "Перезаписать все"),
+    (Some((ConflictChoice::Skip, true)), "Пропустить все"),
+    (Some((ConflictChoice::Rename, true)), "Переименовывать все"),
+    (None, "Отмена"),
+];
+
+#[derive(Clone, Copy)]
+pub enum SortAction {
+    Key(SortKey),
+    Reverse,
+    DirsFirst,
+}
+
+const SORT_MENU: [(SortAction, &str); 9] = [
+    (SortAction::Key(SortKey::Natural), "Имя (file2 < file10)"),
+    (SortAction::Key(SortKey::Name), "Имя (посимвольно)"),
+    (SortAction::Key(SortKey::Size), "Размер"),
+    (SortAction::Key(SortKey::Modified), "Время изменения"),
+    (SortAction::Key(SortKey::Created), "Время создания"),
+    (SortAction::Key(SortKey::Extension), "Расширение"),
+    (SortAction::Key(SortKey::Type), "Тип"),
+    (SortAction::Reverse, "Обратный порядок"),
+    (SortAction::DirsFirst, "Папки сначала"),
+];
+
+// Сколько путей перечислять в окне подтверждения
+const CONFIRM_MAX_NAMES: usize = 5;
+// Сколько переименований показывать в плане перед выполнением
+const RENAME_PREVIEW_LINES: usize = 15;
+
+// Сколько показывать сообщение в строке состояния
+const MESSAGE_TTL: Duration = Duration::from_secs(4);
+const ERROR_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+pub struct Message {
+    level: Level,
+    text: String,
+    shown: Instant,
+}
+
+impl Message {
+    pub fn get_level(&self) -> &Level {
+        &self.level
+    }
+
+    pub fn get_text(&self) -> &String {
+        &self.text
+    }
+
+    fn is_expired(&self) -> bool {
+        let ttl = match self.level {
+            Level::Error => ERROR_TTL,
+            _ => MESSAGE_TTL,
+        };
+        self.shown.elapsed() > ttl
+    }
+}
+
+enum ConfirmAction {
+    Trash(Vec<PathBuf>),
+    Delete(Vec<PathBuf>),
+    Purge(usize),
+    Rename(Vec<(PathBuf, PathBuf)>),
+    Resolve(ConflictChoice, bool),
+}
+
+pub struct Confirm {
+    title: String,
+    lines: Vec<String>,
+    action: ConfirmAction,
+    back: Mode,
+    yes: bool,
+    // объём затрагиваемых элементов, пока он считается в фоне
+    measure: Option<Measure>,
+}
+
+impl Confirm {
+    pub fn get_title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn get_lines(&self) -> &Vec<String> {
+        &self.lines
+    }
+
+    pub fn is_yes(&self) -> bool {
+        self.yes
+    }
+
+    // Первая строка ждёт подсчёта объёма и заменяется, когда он закончится
+    fn poll(&mut self) {
+        let Some(measure) = &self.measure else {
+            return;
+        };
+        if let Some(size) = measure.poll() {
+            self.lines[0] = FileManager::summary(measure.get_count(), Some(size));
+            self.measure = None;
+        }
+    }
+}
+
+pub struct Clipboard {
+    paths: Vec<PathBuf>,
+    cut: bool,
+}
+
+impl Clipboard {
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_cut(&self) -> bool {
+        self.cut
+    }
+}
+
+struct Paste {
+    queue: VecDeque<PathBuf>,
+    planned: Vec<Transfer>,
+    dest: PathBuf,
+    cut: bool,
+    policy: Option<ConflictChoice>,
+}
+
+impl Paste {
+    // Путь занят на диске или другим элементом этой же вставки
+    fn is_taken(&self, target: &Path) -> bool {
+        file_ops::exists(target) || self.planned.iter().any(|planned| planned.target == target)
+    }
+
+    fn plan(&mut self, source: PathBuf, target: PathBuf, choice: ConflictChoice) {
+        let (target, overwrite) = match choice {
+            ConflictChoice::Skip => return,
+            ConflictChoice::Rename if self.is_taken(&target) => {
+                let taken: Vec<PathBuf> = self.planned.iter().map(|p| p.target.clone()).collect();
+                (file_ops::unique_name(&target, &taken), false)
+            }
+            ConflictChoice::Rename => (target, false),
+            ConflictChoice::Overwrite => (target, true),
+        };
+        // перезапись уже запланированного пути заменяет прежний элемент
+        self.planned.retain(|planned| planned.target != target);
+        self.planned.push(Transfer {
+            source,
+            target,
+            overwrite,
+            replaced: None,
+        });
+    }
 }
 
 pub struct FileManager {
-    current_dir: PathBuf,
-    files: Vec<PathBuf>,
-    selected: usize,
+    tabs: Vec<Tab>,
+    tab: usize,
+    visibility: Visibility,
+    sort_selected: usize,
+    view: View,
+    properties: Vec<(&'static str, String)>,
+    permissions: Option<PermissionsDialog>,
+    batch_rename: Option<BatchRenameDialog>,
     content: Option<String>,
-    scroll: usize,
     file_scroll: usize,
     file_lines_count: usize,
     mode: Mode,
@@ -45,23 +306,43 @@ pub struct FileManager {
     input_buffer: String,
     menu_action: Option<MenuAction>,
     menu_selected: usize,
-    context_action: Option<ContextAction>,
     context_selected: usize,
-    search_buffer: String,
+    clipboard: Option<Clipboard>,
+    paste: Option<Paste>,
+    conflict_selected: usize,
+    jobs: JobQueue,
+    journal: Journal,
+    bookmarks: Bookmarks,
+    frecency: Frecency,
+    jump: Option<Jump>,
+    goto: Option<GoTo>,
+    trash_entries: Vec<TrashEntry>,
+    trash_selected: usize,
+    confirm: Option<Confirm>,
+    message: Option<Message>,
+    recursive_search: Option<RecursiveSearch>,
+    content_search: Option<ContentSearch>,
+    content_highlight: Option<(usize, Regex)>,
     editors: Vec<String>,
+    redraw: bool,
 }
 
 impl FileManager {
     pub fn new() -> io::Result<Self> {
         let current_dir = std::env::current_dir()?;
-        let files = Self::get_file_list(&current_dir)?;
-
-        Ok(Self {
-            current_dir,
-            files,
-            selected: 0,
+        let mut visibility = Visibility::load();
+        let tab = Tab::new(current_dir, &mut visibility)?;
+
+        let mut file_manager = Self {
+            tabs: vec![tab],
+            tab: 0,
+            visibility,
+            sort_selected: 0,
+            view: View::default(),
+            properties: Vec::new(),
+            permissions: None,
+            batch_rename: None,
             content: None,
-            scroll: 0,
             file_scroll: 0,
             file_lines_count: 0,
             mode: Mode::Normal,
@@ -69,11 +350,28 @@ impl FileManager {
             input_buffer: String::new(),
             menu_action: None,
             menu_selected: 0,
-            context_action: None,
             context_selected: 0,
-            search_buffer: String::new(),
+            clipboard: None,
+            paste: None,
+            conflict_selected: 0,
+            jobs: JobQueue::default(),
+            journal: Journal::default(),
+            bookmarks: Bookmarks::load(),
+            frecency: Frecency::load(),
+            jump: None,
+            goto: None,
+            trash_entries: Vec::new(),
+            trash_selected: 0,
+            confirm: None,
+            message: None,
+            recursive_search: None,
+            content_search: None,
+            content_highlight: None,
             editors: Self::get_exists_editor_list(),
-        })
+            redraw: false,
+        };
+        file_manager.update_file_list();
+        Ok(file_manager)
     }
 
     // Getters
@@ -85,12 +383,114 @@ impl FileManager {
         &self.input_mode
     }
 
-    pub fn get_selected(&self) -> &usize {
-        &self.selected
+    pub fn get_tabs(&self) -> &Vec<Tab> {
+        &self.tabs
+    }
+
+    pub fn get_tab_index(&self) -> usize {
+        self.tab
+    }
+
+    pub fn get_tab(&self) -> &Tab {
+        &self.tabs[self.tab]
+    }
+
+    fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.tab]
+    }
+
+    pub fn get_panel(&self) -> &Panel {
+        self.get_tab().get_panel()
+    }
+
+    fn panel_mut(&mut self) -> &mut Panel {
+        self.tab_mut().panel_mut()
+    }
+
+    fn get_other_panel(&self) -> &Panel {
+        self.get_tab().get_other_panel()
+    }
+
+    // Фокус переходит на другую панель, превью начинается заново
+    pub fn switch_panel(&mut self) {
+        self.tab_mut().switch_panel();
+        self.content = None;
+        self.content_highlight = None;
+        self.file_scroll = 0;
+    }
+
+    // Tabs
+
+    // Новая вкладка открывается в выделенной папке, а если выделен файл — в текущей
+    pub fn new_tab(&mut self) -> io::Result<()> {
+        let dir = self
+            .get_selected_path()
+            .filter(|path| path.is_dir())
+            .unwrap_or_else(|| self.get_current_dir().clone());
+        let tab = Tab::new(dir, &mut self.visibility)?;
+        self.tabs.insert(self.tab + 1, tab);
+        self.go_to_tab(self.tab + 1)
+    }
+
+    pub fn close_tab(&mut self) -> io::Result<()> {
+        if self.tabs.len() == 1 {
+            self.warn(String::from("Это последняя вкладка"));
+            return Ok(());
+        }
+        self.tabs.remove(self.tab);
+        self.tab = self.tab.min(self.tabs.len() - 1);
+        self.restore_preview()
+    }
+
+    pub fn next_tab(&mut self) -> io::Result<()> {
+        self.go_to_tab((self.tab + 1) % self.tabs.len())
+    }
+
+    pub fn previous_tab(&mut self) -> io::Result<()> {
+        self.go_to_tab((self.tab + self.tabs.len() - 1) % self.tabs.len())
+    }
+
+    // Номер вкладки считается с нуля; прокрутка превью запоминается во вкладке.
+    // Пока вкладка была в фоне, её папки могли измениться или исчезнуть
+    pub fn go_to_tab(&mut self, index: usize) -> io::Result<()> {
+        if index >= self.tabs.len() || index == self.tab {
+            return Ok(());
+        }
+        let file_scroll = self.file_scroll;
+        self.tab_mut().set_file_scroll(file_scroll);
+        self.tab = index;
+        self.refresh_tab(index);
+        self.restore_preview()
+    }
+
+    fn restore_preview(&mut self) -> io::Result<()> {
+        self.content = None;
+        self.open_file()?;
+        self.file_scroll = self.get_tab().get_file_scroll();
+        Ok(())
     }
 
-    pub fn get_files(&self) -> &Vec<PathBuf> {
-        &self.files
+    // Папка активной панели вкладки N (с единицы) для операций вида «в вкладку N»
+    fn get_tab_dir(&self, number: usize) -> io::Result<PathBuf> {
+        number
+            .checked_sub(1)
+            .and_then(|index| self.tabs.get(index))
+            .map(|tab| tab.get_panel().get_current_dir().clone())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("Нет вкладки {}", number))
+            })
+    }
+
+    fn get_selected_path(&self) -> Option<PathBuf> {
+        self.get_panel().get_selected_path().cloned()
+    }
+
+    pub fn get_current_dir(&self) -> &PathBuf {
+        self.get_panel().get_current_dir()
+    }
+
+    pub fn get_message(&self) -> &Option<Message> {
+        &self.message
     }
 
     pub fn get_content(&self) -> &Option<String> {
@@ -105,8 +505,57 @@ impl FileManager {
         &self.input_buffer
     }
 
-    pub fn get_search_buffer(&self) -> &String {
-        &self.search_buffer
+    pub fn get_recursive_search(&self) -> &Option<RecursiveSearch> {
+        &self.recursive_search
+    }
+
+    pub fn get_content_search(&self) -> &Option<ContentSearch> {
+        &self.content_search
+    }
+
+    pub fn get_content_highlight(&self) -> &Option<(usize, Regex)> {
+        &self.content_highlight
+    }
+
+    pub fn get_clipboard(&self) -> &Option<Clipboard> {
+        &self.clipboard
+    }
+
+    pub fn get_jobs(&self) -> &JobQueue {
+        &self.jobs
+    }
+
+    pub fn get_conflict_selected(&self) -> &usize {
+        &self.conflict_selected
+    }
+
+    pub fn get_trash_entries(&self) -> &Vec<TrashEntry> {
+        &self.trash_entries
+    }
+
+    pub fn get_trash_selected(&self) -> &usize {
+        &self.trash_selected
+    }
+
+    pub fn get_confirm(&self) -> &Option<Confirm> {
+        &self.confirm
+    }
+
+    pub fn get_input_title(&self) -> String {
+        match self.menu_action {
+            Some(MenuAction::SelectGlob) => String::from("Шаблон (например *.rs): "),
+            Some(MenuAction::Link(_)) => String::from("Имя ссылки: "),
+            Some(MenuAction::RenameBookmark) => String::from("Имя закладки: "),
+            Some(MenuAction::ImportHistory) => {
+                String::from("Файл zoxide (db.zo), z (.z) или autojump (autojump.txt): ")
+            }
+            Some(MenuAction::CopyTo) => String::from("Копировать в (#N — папка вкладки N): "),
+            Some(MenuAction::MoveTo) => String::from("Переместить в (#N — папка вкладки N): "),
+            Some(MenuAction::IgnorePatterns) => {
+                String::from("Скрывать (например target/ node_modules *.swp): ")
+            }
+            _ => String::from("Введите имя: "),
+        }
     }
 
     pub fn get_menu_selected(&self) -> &usize {
@@ -117,15 +566,6 @@ impl FileManager {
         &self.context_selected
     }
 
-    pub fn get_file_list(path: &Path) -> io::Result<Vec<PathBuf>> {
-        let mut files = fs::read_dir(path)?
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.path())
-            .collect::<Vec<_>>();
-        files.sort();
-        Ok(files)
-    }
-
     pub fn add_to_input_buffer(&mut self, c: char) {
         self.input_buffer.push(c);
     }
@@ -135,16 +575,16 @@ impl FileManager {
     }
 
     pub fn add_to_search_buffer(&mut self, c: char) {
-        self.search_buffer.push(c);
+        self.panel_mut().add_to_search_buffer(c);
     }
 
     pub fn delete_from_search_buffer(&mut self) {
-        self.search_buffer.pop();
+        self.panel_mut().delete_from_search_buffer();
     }
 
     // Navigation
     pub fn enter_handler(&mut self) -> io::Result<()> {
-        if let Some(path) = self.files.get(self.selected) {
+        if let Some(path) = self.get_panel().get_selected_path() {
             if path.is_dir() {
                 self.enter_dir()?;
             } else {
@@ -155,44 +595,83 @@ impl FileManager {
     }
 
     fn enter_dir(&mut self) -> io::Result<()> {
-        if let Some(path) = self.files.get(self.selected) {
-            self.current_dir = path.to_path_buf();
-            self.files = Self::get_file_list(&self.current_dir)?;
-            self.selected = 0;
+        if let Some(path) = self.get_selected_path() {
+            self.load_dir(path)?;
         }
         Ok(())
     }
 
+    // Загружает папку в активную панель и сбрасывает превью.
+    // Посещение попадает в историю для перехода по частым папкам
+    fn load_dir(&mut self, dir: PathBuf) -> io::Result<()> {
+        self.tabs[self.tab]
+            .panel_mut()
+            .load_dir(dir, &mut self.visibility)?;
+        self.file_scroll = 0;
+        self.content = None;
+        let dir = self.get_current_dir().clone();
+        self.frecency.visit(&dir);
+        Ok(())
+    }
+
+    pub fn go_back(&mut self) -> io::Result<()> {
+        let moved = self.tabs[self.tab]
+            .panel_mut()
+            .go_back(&mut self.visibility)?;
+        self.after_history_move(moved, "Дальше назад некуда");
+        Ok(())
+    }
+
+    pub fn go_forward(&mut self) -> io::Result<()> {
+        let moved = self.tabs[self.tab]
+            .panel_mut()
+            .go_forward(&mut self.visibility)?;
+        self.after_history_move(moved, "Дальше вперёд некуда");
+        Ok(())
+    }
+
+    fn after_history_move(&mut self, moved: bool, empty: &str) {
+        if moved {
+            self.file_scroll = 0;
+            self.content = None;
+        } else {
+            self.warn(empty.to_string());
+        }
+    }
+
     fn open_file(&mut self) -> io::Result<()> {
-        if let Some(path) = self.files.get(self.selected) {
+        if let Some(path) = self.get_selected_path() {
+            self.content_highlight = None;
+            self.content = None;
+            self.file_lines_count = 0;
             if path.is_file() {
-                self.content = Some(fs::read_to_string(path)?);
-                self.file_lines_count = Self::get_file_lines_count(path);
-            } else {
-                self.content = None
+                // файлы не в UTF-8 показываются с заменой некорректных байтов
+                let content = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+                self.file_lines_count = content.lines().count();
+                self.content = Some(content);
             }
         }
         Ok(())
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_parent_dir(&mut self) -> io::Result<()> {
-        if let Some(parent) = self.current_dir.parent() {
-            self.current_dir = parent.to_path_buf();
-            self.files = Self::get_file_list(&self.current_dir)?;
-            self.selected = 0;
-            self.content = None;
+        let child = self.get_current_dir().clone();
+        if let Some(parent) = child.parent() {
+            self.load_dir(parent.to_path_buf())?;
+            // курсор встаёт на папку, из которой только что вышли
+            self.panel_mut().focus(&child);
         }
         Ok(())
     }
 
-    pub fn up(&mut self) {
-        if self.selected > 0 {
-            self.selected -= 1;
-        } else {
-            self.selected = self.files.len() - 1;
+    pub fn up(&mut self) -> io::Result<()> {
+        if self.get_panel().get_files().is_empty() {
+            return Ok(());
         }
+        self.panel_mut().up();
         self.file_scroll = 0;
-        self.open_file().unwrap();
+        self.open_file()
     }
 
     pub fn menu_up(&mut self) {
@@ -207,7 +686,7 @@ impl FileManager {
         if self.context_selected > 0 {
             self.context_selected -= 1
         } else {
-            self.context_selected = self.show_menu().len() - 1;
+            self.context_selected = self.show_context().len() - 1;
         }
     }
 
@@ -217,21 +696,17 @@ impl FileManager {
         }
     }
 
-    pub fn down(&mut self) {
-        if self.selected < self.files.len() - 1 {
-            self.selected += 1;
-            self.file_scroll = 0;
-            self.open_file().unwrap();
-        } else {
-            self.selected = 0
+    pub fn down(&mut self) -> io::Result<()> {
+        if self.get_panel().get_files().is_empty() {
+            return Ok(());
         }
-
+        self.panel_mut().down();
         self.file_scroll = 0;
-        self.open_file().unwrap();
+        self.open_file()
     }
 
     pub fn menu_down(&mut self) {
-        if self.menu_selected < self.show_menu().len() - 1 {
+        if self.menu_selected + 1 < self.show_menu().len() {
             self.menu_selected += 1;
         } else {
             self.menu_selected = 0;
@@ -239,7 +714,7 @@ impl FileManager {
     }
 
     pub fn context_down(&mut self) {
-        if self.context_selected < self.show_context().len() - 1 {
+        if self.context_selected + 1 < self.show_context().len() {
             self.context_selected += 1;
         } else {
             self.context_selected = 0;
@@ -247,223 +722,1686 @@ impl FileManager {
     }
 
     pub fn page_down(&mut self) {
-        if self.file_scroll <= self.file_lines_count - 1 {
+        if self.file_scroll < self.file_lines_count {
             self.file_scroll += 2;
         }
     }
 
-    pub fn find_in_current_dir(&mut self) {
-        let file_name = self.search_buffer.trim();
-        if file_name.is_empty() {
-            return;
-        }
-
-        let matcher = SkimMatcherV2::default();
-
-        if let Some((index, _)) = self.files.iter().enumerate().find(|(_, path)| {
-            path.file_name() // Получаем имя файла
-                .and_then(|os_str| os_str.to_str())
-                .map_or(false, |name| matcher.fuzzy_match(name, file_name).is_some())
-        }) {
-            self.selected = index;
-        } else {
-            println!("No file matches '{}'.", file_name);
-        }
+    // Фильтр остаётся активным до Esc
+    pub fn confirm_filter(&mut self) {
+        self.default_mode();
+    }
 
-        self.search_buffer.clear();
-        self.default_input_mode();
+    pub fn clear_filter(&mut self) {
+        self.panel_mut().clear_filter();
         self.default_mode();
     }
 
-    fn get_file_lines_count(path: &PathBuf) -> usize {
-        let file = File::open(path).expect("Open file error");
-        let reader = BufReader::new(file);
-        reader.lines().count()
+    // Marks
+
+    pub fn toggle_mark(&mut self) -> io::Result<()> {
+        if self.panel_mut().toggle_mark() {
+            self.down()?;
+        }
+        Ok(())
     }
 
-    //  Modes
-    pub fn menu_mode(&mut self) {
-        self.mode = Mode::Menu;
+    pub fn toggle_visual(&mut self) {
+        self.panel_mut().toggle_visual();
     }
 
-    pub fn default_mode(&mut self) {
-        self.mode = Mode::Normal;
+    pub fn cancel_visual(&mut self) {
+        self.panel_mut().cancel_visual();
     }
 
-    pub fn search_mode(&mut self) {
-        self.mode = Mode::Search;
+    pub fn invert_marks(&mut self) {
+        self.panel_mut().invert_marks();
     }
 
-    pub fn context_mode(&mut self) {
-        self.mode = Mode::Context
+    pub fn clear_marks(&mut self) {
+        self.panel_mut().clear_marks();
     }
 
-    pub fn input_mode(&mut self) {
-        self.input_mode = InputMode::Input
+    pub fn select_glob_prompt(&mut self) {
+        self.menu_mode();
+        self.input_mode();
+        self.menu_action = Some(MenuAction::SelectGlob);
     }
 
-    pub fn default_input_mode(&mut self) {
-        self.input_mode = InputMode::Normal
+    fn mark_by_glob(&mut self) -> io::Result<()> {
+        let buffer = std::mem::take(&mut self.input_buffer);
+        let pattern = buffer.trim();
+        self.default_mode();
+        if !pattern.is_empty() {
+            let matcher = Glob::new(pattern)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?
+                .compile_matcher();
+            self.panel_mut().mark_where(|path| {
+                path.file_name()
+                    .is_some_and(|name| matcher.is_match(Path::new(name)))
+            });
+        }
+        Ok(())
     }
 
-    // Menu
+    // Sort
 
-    pub fn show_menu(&self) -> Vec<&str> {
-        vec![
-            "Удалить",
-            "Создать файл",
-            "Создать папку",
-            "Переименовать",
-            "Отмена",
-        ]
+    pub fn get_sort_selected(&self) -> &usize {
+        &self.sort_selected
     }
 
-    pub fn show_context(&self) -> Vec<String> {
-        let mut editor_list_with_prefix: Vec<String> = self
-            .editors
+    pub fn sort_mode(&mut self) {
+        self.mode = Mode::Sort;
+    }
+
+    // Пункты меню сортировки с отметкой выбранных
+    pub fn show_sort(&self) -> Vec<String> {
+        let sort = self.get_panel().get_sort();
+        SORT_MENU
             .iter()
-            .map(|e| format!("Открыть в {}", e))
-            .collect();
-        editor_list_with_prefix.push(String::from("Отмена"));
-        editor_list_with_prefix
+            .map(|(action, label)| {
+                let checked = match action {
+                    SortAction::Key(key) => sort.key == *key,
+                    SortAction::Reverse => sort.reverse,
+                    SortAction::DirsFirst => sort.dirs_first,
+                };
+                format!("[{}] {}", if checked { "x" } else { " " }, label)
+            })
+            .collect()
+    }
 
-        // vec![
-        //     "Открыть в Nano",
-        //     "Открыть в Vim",
-        //     "Открыть в WebStorm",
-        //     "Открыть в RustRover",
-        //     "Открыть в VS Code",
-        //     "Отмена",
-        // ]
+    pub fn sort_up(&mut self) {
+        if self.sort_selected > 0 {
+            self.sort_selected -= 1;
+        } else {
+            self.sort_selected = SORT_MENU.len() - 1;
+        }
     }
 
-    pub fn select_from_menu(&mut self) -> io::Result<()> {
-        match self.menu_selected {
-            0 => self.delete_selected()?,
-            1 => {
-                self.input_mode();
-                self.menu_action = Option::from(MenuAction::CreateFile);
-            }
-            2 => {
-                self.input_mode();
-                self.menu_action = Option::from(MenuAction::CreateDir);
-            }
-            3 => {
-                self.input_mode();
-                self.menu_action = Option::from(MenuAction::Rename);
+    pub fn sort_down(&mut self) {
+        if self.sort_selected + 1 < SORT_MENU.len() {
+            self.sort_selected += 1;
+        } else {
+            self.sort_selected = 0;
+        }
+    }
+
+    // Сортировка запоминается для текущей папки, выделенный элемент остаётся выделенным
+    pub fn select_from_sort(&mut self) -> io::Result<()> {
+        let mut sort = self.get_panel().get_sort();
+        match SORT_MENU[self.sort_selected].0 {
+            SortAction::Key(key) => {
+                sort.key = key;
+                self.default_mode();
             }
-            _ => self.default_input_mode(),
+            SortAction::Reverse => sort.reverse = !sort.reverse,
+            SortAction::DirsFirst => sort.dirs_first = !sort.dirs_first,
         }
-        Ok(())
+        self.tabs[self.tab]
+            .panel_mut()
+            .set_sort(sort, &mut self.visibility)
     }
 
-    pub fn select_from_context(&mut self) -> io::Result<()> {
-        let file_path = self.files.get(self.selected);
-        let current_editor = self.editors.get(self.context_selected);
+    // View
 
-        if let Some(editor) = current_editor {
-            if let Some(file) = file_path {
-                Self::opn_in_editor(file, editor)?;
-            }
-        } else {
-            self.default_mode()
+    pub fn get_view(&self) -> &View {
+        &self.view
+    }
+
+    pub fn toggle_detailed(&mut self) {
+        self.view.toggle_detailed();
+    }
+
+    // Настройка колонок включает подробный вид, чтобы изменения были видны сразу
+    pub fn columns_mode(&mut self) {
+        if !self.view.is_detailed() {
+            self.view.toggle_detailed();
         }
+        self.mode = Mode::Columns;
+    }
 
-        // match self.context_selected {
-        //     0 => {
-        //         if let Some(path) = file_path  {
-        //             Self::opn_in_editor(path, current_editor)?;
-        //         }
-        //     }
-        //     1 => println!("Open in Vim"),
-        //     2 => println!("Open in WS"),
-        //     3 => println!("Open in RustRover"),
-        //     4 => println!("Open in VS CODE"),
-        //     _ => self.default_mode(),
-        // }
-        Ok(())
+    pub fn columns_up(&mut self) {
+        self.view.up();
     }
 
-    pub fn handle_menu_action(&mut self) -> io::Result<()> {
-        if let Some(action) = self.menu_action.take() {
-            match action {
-                MenuAction::CreateFile => self.create_file()?,
-                MenuAction::CreateDir => self.create_dir()?,
-                MenuAction::Rename => self.rename_selected()?,
-                _ => {}
-            }
-        };
-        self.default_input_mode();
-        Ok(())
+    pub fn columns_down(&mut self) {
+        self.view.down();
+    }
+
+    pub fn toggle_column(&mut self) {
+        self.view.toggle_selected();
+    }
+
+    pub fn resize_column(&mut self, delta: i16) {
+        self.view.resize_selected(delta);
     }
 
-    fn update_file_list(&mut self) -> io::Result<()> {
-        let mut files = fs::read_dir(&self.current_dir)?
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.path())
-            .collect::<Vec<_>>();
-        files.sort();
+    // Properties
 
-        self.files = files;
-        Ok(())
+    pub fn get_properties(&self) -> &Vec<(&'static str, String)> {
+        &self.properties
     }
 
-    fn create_file(&mut self) -> io::Result<()> {
-        let file_name = self.input_buffer.trim();
-        if !file_name.is_empty() {
-            let file_path = self.current_dir.join(file_name);
-            File::create(file_path)?;
-            self.update_file_list()?;
+    pub fn properties_mode(&mut self) -> io::Result<()> {
+        if let Some(path) = self.get_panel().get_selected_path() {
+            self.properties = properties::inspect(path)?;
+            self.mode = Mode::Properties;
+        } else {
+            self.default_mode();
         }
-        self.input_buffer.clear();
         Ok(())
     }
 
-    fn create_dir(&mut self) -> io::Result<()> {
-        let dir_name = self.input_buffer.trim();
-        if !dir_name.is_empty() {
-            let dir_path = self.current_dir.join(dir_name);
-            fs::create_dir(dir_path)?;
-            self.update_file_list()?;
+    // Permissions
+
+    pub fn get_permissions(&self) -> &Option<PermissionsDialog> {
+        &self.permissions
+    }
+
+    pub fn permissions_mode(&mut self) -> io::Result<()> {
+        let targets = self.get_targets();
+        if targets.is_empty() {
+            self.default_mode();
+            return Ok(());
         }
-        self.input_buffer.clear();
+        self.permissions = Some(PermissionsDialog::new(targets)?);
+        self.default_input_mode();
+        self.mode = Mode::Permissions;
         Ok(())
     }
 
-    fn rename_selected(&mut self) -> io::Result<()> {
-        let new_name = self.input_buffer.trim();
-        if let Some(path) = self.files.get(self.selected) {
-            if !new_name.is_empty() {
-                let new_path = path.parent().unwrap().join(new_name);
-                fs::rename(path, new_path)?;
-                self.update_file_list()?;
-            }
+    pub fn permissions_next(&mut self) {
+        if let Some(dialog) = &mut self.permissions {
+            dialog.next();
         }
-        self.input_buffer.clear();
-        self.update_file_list()?;
-        Ok(())
     }
 
-    fn delete_selected(&mut self) -> io::Result<()> {
-        if let Some(path) = self.files.get(self.selected) {
-            if path.is_dir() {
-                fs::remove_dir_all(path)?;
-            } else {
-                fs::remove_file(path)?;
-            }
+    pub fn permissions_previous(&mut self) {
+        if let Some(dialog) = &mut self.permissions {
+            dialog.previous();
+        }
+    }
+
+    pub fn permissions_toggle(&mut self) {
+        if let Some(dialog) = &mut self.permissions {
+            dialog.toggle();
+        }
+    }
+
+    pub fn permissions_push(&mut self, c: char) {
+        if let Some(dialog) = &mut self.permissions {
+            dialog.push(c);
+        }
+    }
+
+    pub fn permissions_pop(&mut self) {
+        if let Some(dialog) = &mut self.permissions {
+            dialog.pop();
+        }
+    }
+
+    // Изменения применяются фоновой задачей: рекурсивный обход может быть долгим
+    pub fn apply_permissions(&mut self) -> io::Result<()> {
+        let Some(dialog) = &self.permissions else {
+            return Ok(());
+        };
+        let attributes = dialog.attributes()?;
+        let targets = dialog.get_targets().clone();
+        let title = format!("Права {}", targets.len());
+        self.jobs
+            .submit(title, Operation::SetAttributes(targets, attributes));
+        self.close_permissions();
+        self.clear_marks();
+        Ok(())
+    }
+
+    pub fn close_permissions(&mut self) {
+        self.permissions = None;
+        self.default_mode();
+    }
+
+    // Batch rename
+
+    pub fn get_batch_rename(&self) -> &Option<BatchRenameDialog> {
+        &self.batch_rename
+    }
+
+    pub fn batch_rename_mode(&mut self) {
+        // {n} нумерует элементы в том порядке, в каком они видны в списке
+        let mut targets = self.get_targets();
+        let order: HashMap<&PathBuf, usize> = self
+            .get_panel()
+            .get_files()
+            .iter()
+            .enumerate()
+            .map(|(index, path)| (path, index))
+            .collect();
+        targets.sort_by_key(|target| order.get(target).copied().unwrap_or(usize::MAX));
+        if targets.is_empty() {
+            self.default_mode();
+            return;
+        }
+        self.batch_rename = Some(BatchRenameDialog::new(targets));
+        self.default_input_mode();
+        self.mode = Mode::BatchRename;
+    }
+
+    pub fn batch_rename_next(&mut self) {
+        if let Some(dialog) = &mut self.batch_rename {
+            dialog.next();
+        }
+    }
+
+    pub fn batch_rename_previous(&mut self) {
+        if let Some(dialog) = &mut self.batch_rename {
+            dialog.previous();
+        }
+    }
+
+    pub fn batch_rename_toggle(&mut self) {
+        if let Some(dialog) = &mut self.batch_rename {
+            dialog.toggle();
+        }
+    }
+
+    pub fn batch_rename_push(&mut self, c: char) {
+        if let Some(dialog) = &mut self.batch_rename {
+            dialog.push(c);
+        }
+    }
+
+    pub fn batch_rename_pop(&mut self) {
+        if let Some(dialog) = &mut self.batch_rename {
+            dialog.pop();
+        }
+    }
+
+    // При совпадающих именах окно остаётся открытым, чтобы шаблон можно было поправить
+    pub fn apply_batch_rename(&mut self) -> io::Result<()> {
+        let Some(dialog) = &self.batch_rename else {
+            return Ok(());
+        };
+        let Some(renames) = dialog.renames() else {
+            self.warn(String::from(
+                "Переименование невозможно: исправьте ошибку или совпадающие имена",
+            ));
+            return Ok(());
+        };
+        if renames.is_empty() {
+            self.warn(String::from("Имена не изменились"));
+            return Ok(());
+        }
+        let steps = rename::plan(renames)?;
+        self.close_batch_rename();
+        self.apply_renames(steps)
+    }
+
+    pub fn close_batch_rename(&mut self) {
+        self.batch_rename = None;
+        self.default_mode();
+    }
+
+    // Bookmarks
+
+    pub fn get_bookmarks(&self) -> &Bookmarks {
+        &self.bookmarks
+    }
+
+    // После m ждём букву закладки, Enter вместо буквы открывает меню
+    pub fn set_mark_mode(&mut self) {
+        self.mode = Mode::SetMark;
+        self.info(String::from(
+            "Закладка: буква — запомнить папку, Enter — меню",
+        ));
+    }
+
+    pub fn jump_mark_mode(&mut self) {
+        self.mode = Mode::JumpMark;
+        self.info(String::from(
+            "Перейти к закладке: буква, ' — список закладок",
+        ));
+    }
+
+    pub fn cancel_pending(&mut self) {
+        self.message = None;
+        self.default_mode();
+    }
+
+    pub fn set_bookmark(&mut self, key: char) -> io::Result<()> {
+        self.default_mode();
+        let dir = self.get_current_dir().clone();
+        self.bookmarks.set(key, &dir)?;
+        self.info(format!("Закладка {}: {}", key, dir.display()));
+        Ok(())
+    }
+
+    pub fn jump_to_bookmark(&mut self, key: char) -> io::Result<()> {
+        self.default_mode();
+        match self
+            .bookmarks
+            .get(key)
+            .map(|bookmark| bookmark.path.clone())
+        {
+            Some(path) => self.open_bookmark(path),
+            None => {
+                self.warn(format!("Нет закладки {}", key));
+                Ok(())
+            }
+        }
+    }
+
+    fn open_bookmark(&mut self, path: PathBuf) -> io::Result<()> {
+        if !path.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Папки закладки больше нет: {}", path.display()),
+            ));
+        }
+        self.message = None;
+        self.load_dir(path)
+    }
+
+    pub fn bookmarks_mode(&mut self) {
+        self.message = None;
+        self.mode = Mode::Bookmarks;
+    }
+
+    pub fn bookmarks_up(&mut self) {
+        self.bookmarks.up();
+    }
+
+    pub fn bookmarks_down(&mut self) {
+        self.bookmarks.down();
+    }
+
+    pub fn select_from_bookmarks(&mut self) -> io::Result<()> {
+        let path = self
+            .bookmarks
+            .get_selected_bookmark()
+            .map(|bookmark| bookmark.path.clone());
+        if let Some(path) = path {
+            self.default_mode();
+            self.open_bookmark(path)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_bookmark(&mut self) -> io::Result<()> {
+        self.bookmarks.remove_selected()
+    }
+
+    pub fn rename_bookmark_prompt(&mut self) {
+        if let Some(bookmark) = self.bookmarks.get_selected_bookmark() {
+            self.input_buffer = bookmark.name.clone();
+            self.menu_mode();
+            self.input_mode();
+            self.menu_action = Some(MenuAction::RenameBookmark);
+        }
+    }
+
+    // После переименования возвращаемся в список закладок
+    fn rename_bookmark(&mut self) -> io::Result<()> {
+        let buffer = std::mem::take(&mut self.input_buffer);
+        self.mode = Mode::Bookmarks;
+        if !buffer.trim().is_empty() {
+            self.bookmarks.rename_selected(buffer.trim())?;
+        }
+        Ok(())
+    }
+
+    // Frecency jump
+
+    pub fn get_jump(&self) -> &Option<Jump> {
+        &self.jump
+    }
+
+    pub fn jump_mode(&mut self) {
+        self.jump = Some(Jump::new(&self.frecency, self.get_current_dir()));
+        self.mode = Mode::Jump;
+    }
+
+    pub fn jump_up(&mut self) {
+        if let Some(jump) = &mut self.jump {
+            jump.up();
+        }
+    }
+
+    pub fn jump_down(&mut self) {
+        if let Some(jump) = &mut self.jump {
+            jump.down();
+        }
+    }
+
+    pub fn add_to_jump(&mut self, c: char) {
+        if let Some(jump) = &mut self.jump {
+            jump.add_to_query(c);
+        }
+    }
+
+    pub fn delete_from_jump(&mut self) {
+        if let Some(jump) = &mut self.jump {
+            jump.delete_from_query();
+        }
+    }
+
+    // Папку могли удалить после загрузки базы — тогда она забывается
+    pub fn select_from_jump(&mut self) -> io::Result<()> {
+        let path = self
+            .jump
+            .as_ref()
+            .and_then(|jump| jump.get_selected_path())
+            .cloned();
+        self.close_jump();
+        let Some(path) = path else {
+            return Ok(());
+        };
+        if !path.is_dir() {
+            self.frecency.remove(&path)?;
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Папки больше нет: {}", path.display()),
+            ));
+        }
+        self.load_dir(path)
+    }
+
+    pub fn close_jump(&mut self) {
+        self.jump = None;
+        self.default_mode();
+    }
+
+    pub fn import_history_prompt(&mut self) {
+        self.input_buffer = frecency::import_candidates()
+            .first()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        self.input_mode();
+        self.menu_action = Some(MenuAction::ImportHistory);
+    }
+
+    fn import_history(&mut self) -> io::Result<()> {
+        let buffer = std::mem::take(&mut self.input_buffer);
+        self.default_mode();
+        if buffer.trim().is_empty() {
+            return Ok(());
+        }
+        let count = self.frecency.import(Path::new(buffer.trim()))?;
+        self.info(format!("Импортировано папок: {}", count));
+        Ok(())
+    }
+
+    // Go to path
+
+    pub fn get_goto(&self) -> &Option<GoTo> {
+        &self.goto
+    }
+
+    pub fn goto_mode(&mut self) {
+        self.goto = Some(GoTo::default());
+        self.mode = Mode::GoTo;
+    }
+
+    pub fn add_to_goto(&mut self, c: char) {
+        if let Some(goto) = &mut self.goto {
+            goto.push(c);
+        }
+    }
+
+    pub fn delete_from_goto(&mut self) {
+        if let Some(goto) = &mut self.goto {
+            goto.pop();
+        }
+    }
+
+    pub fn complete_goto(&mut self) {
+        let current_dir = self.get_current_dir().clone();
+        if let Some(goto) = &mut self.goto {
+            goto.complete(&current_dir);
+        }
+    }
+
+    pub fn goto_up(&mut self) {
+        if let Some(goto) = &mut self.goto {
+            goto.up();
+        }
+    }
+
+    pub fn goto_down(&mut self) {
+        if let Some(goto) = &mut self.goto {
+            goto.down();
+        }
+    }
+
+    // Ошибки показываются в самой строке ввода, окно остаётся открытым.
+    // Для файла открывается его папка, а курсор встаёт на файл
+    pub fn select_from_goto(&mut self) {
+        let current_dir = self.get_current_dir().clone();
+        let Some(path) = self
+            .goto
+            .as_mut()
+            .and_then(|goto| goto.accept(&current_dir))
+        else {
+            return;
+        };
+
+        let result = match path.parent() {
+            Some(parent) if !path.is_dir() => self
+                .load_dir(parent.to_path_buf())
+                .map(|_| self.panel_mut().focus(&path)),
+            _ => self.load_dir(path.clone()).map(|_| true),
+        };
+        match result {
+            Ok(focused) => {
+                self.close_goto();
+                if !focused {
+                    self.warn_hidden(&path);
+                }
+            }
+            Err(err) => {
+                if let Some(goto) = &mut self.goto {
+                    goto.set_error(format!("{}: {}", path.display(), err));
+                }
+            }
+        }
+    }
+
+    pub fn close_goto(&mut self) {
+        self.goto = None;
+        self.default_mode();
+    }
+
+    // Visibility
+
+    pub fn get_visibility(&self) -> &Visibility {
+        &self.visibility
+    }
+
+    pub fn toggle_hidden(&mut self) -> io::Result<()> {
+        self.visibility.toggle_hidden();
+        self.update_file_list();
+        Ok(())
+    }
+
+    pub fn toggle_ignore_files(&mut self) -> io::Result<()> {
+        self.visibility.toggle_ignore_files();
+        self.update_file_list();
+        Ok(())
+    }
+
+    pub fn ignore_patterns_prompt(&mut self) {
+        self.input_buffer = self.visibility.get_patterns().join(" ");
+        self.menu_mode();
+        self.input_mode();
+        self.menu_action = Some(MenuAction::IgnorePatterns);
+    }
+
+    fn set_ignore_patterns(&mut self) -> io::Result<()> {
+        let buffer = std::mem::take(&mut self.input_buffer);
+        self.default_mode();
+        self.visibility.set_patterns(&buffer)?;
+        self.update_file_list();
+        Ok(())
+    }
+
+    pub fn is_visual(&self) -> bool {
+        self.get_panel().is_visual()
+    }
+
+    // Отмеченные элементы активной панели, а если их нет — выделенный
+    pub fn get_targets(&self) -> Vec<PathBuf> {
+        self.get_panel().get_targets()
+    }
+
+    // Recursive search
+
+    pub fn recursive_search_up(&mut self) {
+        if let Some(search) = &mut self.recursive_search {
+            search.up();
+        }
+    }
+
+    pub fn recursive_search_down(&mut self) {
+        if let Some(search) = &mut self.recursive_search {
+            search.down();
+        }
+    }
+
+    pub fn add_to_recursive_search(&mut self, c: char) {
+        if let Some(search) = &mut self.recursive_search {
+            search.add_to_query(c);
+        }
+    }
+
+    pub fn delete_from_recursive_search(&mut self) {
+        if let Some(search) = &mut self.recursive_search {
+            search.delete_from_query();
+        }
+    }
+
+    pub fn recursive_search_root_to_parent(&mut self) {
+        if let Some(search) = &mut self.recursive_search {
+            search.root_to_parent();
+        }
+    }
+
+    pub fn select_from_recursive_search(&mut self) -> io::Result<()> {
+        let path = self
+            .recursive_search
+            .as_ref()
+            .and_then(|search| search.get_selected_path())
+            .cloned();
+
+        if let Some(path) = path {
+            self.select_path(&path)?;
+            self.close_recursive_search();
+        }
+        Ok(())
+    }
+
+    pub fn close_recursive_search(&mut self) {
+        self.recursive_search = None;
+        self.default_mode();
+    }
+
+    // Content search
+
+    pub fn content_search_up(&mut self) {
+        if let Some(search) = &mut self.content_search {
+            search.up();
+        }
+    }
+
+    pub fn content_search_down(&mut self) {
+        if let Some(search) = &mut self.content_search {
+            search.down();
+        }
+    }
+
+    pub fn add_to_content_search(&mut self, c: char) {
+        if let Some(search) = &mut self.content_search {
+            search.add_to_pattern(c);
+        }
+    }
+
+    pub fn delete_from_content_search(&mut self) {
+        if let Some(search) = &mut self.content_search {
+            search.delete_from_pattern();
+        }
+    }
+
+    pub fn toggle_content_search_regex(&mut self) {
+        if let Some(search) = &mut self.content_search {
+            search.toggle_regex();
+        }
+    }
+
+    // Enter запускает поиск, а если шаблон не менялся — открывает выбранное совпадение
+    pub fn content_search_enter(&mut self) -> io::Result<()> {
+        let Some(search) = &mut self.content_search else {
+            return Ok(());
+        };
+        if search.is_dirty() {
+            search.start();
+            return Ok(());
+        }
+
+        let target = search
+            .get_selected_match()
+            .map(|hit| (hit.path.clone(), hit.line))
+            .zip(search.get_regex().clone());
+
+        if let Some(((path, line), regex)) = target {
+            // под курсором оказался бы другой файл — открывать его нельзя
+            if self.select_path(&path)? {
+                self.open_file()?;
+                self.file_scroll = line.saturating_sub(CONTEXT_LINES + 1);
+                self.content_highlight = Some((line, regex));
+            }
+            self.close_content_search();
+        }
+        Ok(())
+    }
+
+    pub fn close_content_search(&mut self) {
+        self.content_search = None;
+        self.default_mode();
+    }
+
+    // Переходит в родительскую папку пути и выделяет его.
+    // false — путь скрыт фильтром и курсор остался на другом элементе
+    pub fn select_path(&mut self, path: &Path) -> io::Result<bool> {
+        let Some(parent) = path.parent() else {
+            return Ok(false);
+        };
+        self.load_dir(parent.to_path_buf())?;
+        if self.panel_mut().focus(path) {
+            return Ok(true);
+        }
+        self.warn_hidden(path);
+        Ok(false)
+    }
+
+    fn warn_hidden(&mut self, path: &Path) {
+        self.warn(format!(
+            "{} скрыт фильтром или настройками показа",
+            path.display()
+        ));
+    }
+
+    // Вызывается на каждой итерации главного цикла.
+    // Возвращает true, если с прошлого раза экран мог измениться сам по себе
+    pub fn tick(&mut self) -> bool {
+        let mut changed = !self.jobs.is_empty();
+        if let Some(search) = &mut self.recursive_search {
+            changed |= search.is_running();
+            search.poll();
+        }
+        if let Some(search) = &mut self.content_search {
+            changed |= search.is_running();
+            search.poll();
+        }
+        if let Some(confirm) = &mut self.confirm {
+            changed |= confirm.measure.is_some();
+            confirm.poll();
+        }
+        // после завершения задачи содержимое папки могло измениться
+        if self.jobs.poll() {
+            for recorded in self.jobs.take_recorded() {
+                if let (Step::Do, Some(Change::Move(transfers))) = (recorded.step, &recorded.done) {
+                    self.forget_moved(transfers);
+                }
+                if let Err(err) = self.journal.finish(recorded) {
+                    self.error(&err);
+                }
+            }
+            self.update_file_list();
+        }
+        if matches!(self.mode, Mode::Jobs) && self.jobs.is_empty() {
+            self.default_mode();
+        }
+        if self.message.as_ref().is_some_and(Message::is_expired) {
+            self.message = None;
+            changed = true;
+        }
+        // не записанная история не должна мешать работе
+        let _ = self.frecency.save_if_due();
+        changed
+    }
+
+    // Messages
+
+    fn notify(&mut self, level: Level, text: String) {
+        self.message = Some(Message {
+            level,
+            text,
+            shown: Instant::now(),
+        });
+    }
+
+    pub fn info(&mut self, text: String) {
+        self.notify(Level::Info, text);
+    }
+
+    pub fn warn(&mut self, text: String) {
+        self.notify(Level::Warn, text);
+    }
+
+    pub fn error(&mut self, err: &io::Error) {
+        self.notify(Level::Error, err.to_string());
+    }
+
+    // Jobs
+
+    pub fn jobs_up(&mut self) {
+        self.jobs.up();
+    }
+
+    pub fn jobs_down(&mut self) {
+        self.jobs.down();
+    }
+
+    pub fn cancel_job(&mut self) {
+        self.jobs.cancel_selected();
+    }
+
+    //  Modes
+    pub fn menu_mode(&mut self) {
+        self.mode = Mode::Menu;
+    }
+
+    pub fn default_mode(&mut self) {
+        self.mode = Mode::Normal;
+    }
+
+    pub fn search_mode(&mut self) {
+        self.mode = Mode::Search;
+    }
+
+    pub fn recursive_search_mode(&mut self) {
+        self.recursive_search = Some(RecursiveSearch::new(self.get_current_dir().clone()));
+        self.mode = Mode::RecursiveSearch;
+    }
+
+    pub fn content_search_mode(&mut self) {
+        self.content_search = Some(ContentSearch::new(self.get_current_dir().clone()));
+        self.mode = Mode::ContentSearch;
+    }
+
+    pub fn jobs_mode(&mut self) {
+        if !self.jobs.is_empty() {
+            self.mode = Mode::Jobs;
+        }
+    }
+
+    pub fn context_mode(&mut self) {
+        self.mode = Mode::Context
+    }
+
+    pub fn input_mode(&mut self) {
+        self.input_mode = InputMode::Input
+    }
+
+    pub fn default_input_mode(&mut self) {
+        self.input_mode = InputMode::Normal
+    }
+
+    // Menu
+
+    pub fn show_menu(&self) -> Vec<&str> {
+        MENU.iter().map(|(_, label)| *label).collect()
+    }
+
+    pub fn show_conflict(&self) -> Vec<&str> {
+        CONFLICT_MENU.iter().map(|(_, label)| *label).collect()
+    }
+
+    pub fn show_context(&self) -> Vec<String> {
+        let mut editor_list_with_prefix: Vec<String> = self
+            .editors
+            .iter()
+            .map(|e| format!("Открыть в {}", e))
+            .collect();
+        editor_list_with_prefix.push(String::from("Отмена"));
+        editor_list_with_prefix
+
+        // vec![
+        //     "Открыть в Nano",
+        //     "Открыть в Vim",
+        //     "Открыть в WebStorm",
+        //     "Открыть в RustRover",
+        //     "Открыть в VS Code",
+        //     "Отмена",
+        // ]
+    }
+
+    pub fn select_from_menu(&mut self) -> io::Result<()> {
+        let action = MENU
+            .get(self.menu_selected)
+            .map_or(MenuAction::Cancel, |(action, _)| *action);
+        match action {
+            MenuAction::Delete => self.trash_selected(),
+            MenuAction::DeletePermanently => self.delete_permanently_prompt(),
+            MenuAction::Trash => self.trash_mode(),
+            MenuAction::Properties => self.properties_mode()?,
+            MenuAction::Permissions => self.permissions_mode()?,
+            MenuAction::BulkRename => {
+                self.default_mode();
+                self.bulk_rename()?
+            }
+            MenuAction::PatternRename => self.batch_rename_mode(),
+            MenuAction::CreateFile | MenuAction::CreateDir | MenuAction::Rename => {
+                self.input_mode();
+                self.menu_action = Some(action);
+            }
+            MenuAction::Link(_) => self.link_prompt(action),
+            MenuAction::Copy => self.yank_selected(false),
+            MenuAction::Cut => self.yank_selected(true),
+            MenuAction::Paste => self.paste()?,
+            MenuAction::CopyTo => self.transfer_prompt(false),
+            MenuAction::MoveTo => self.transfer_prompt(true),
+            MenuAction::ImportHistory => self.import_history_prompt(),
+            _ => self.default_input_mode(),
+        }
+        Ok(())
+    }
+
+    pub fn select_from_context(&mut self) -> io::Result<()> {
+        let targets = self.get_targets();
+        let current_editor = self.editors.get(self.context_selected);
+
+        if let Some(editor) = current_editor {
+            if !targets.is_empty() {
+                Self::opn_in_editor(&targets, editor)?;
+            }
+        } else {
+            self.default_mode()
+        }
+
+        // match self.context_selected {
+        //     0 => {
+        //         if let Some(path) = file_path  {
+        //             Self::opn_in_editor(path, current_editor)?;
+        //         }
+        //     }
+        //     1 => println!("Open in Vim"),
+        //     2 => println!("Open in WS"),
+        //     3 => println!("Open in RustRover"),
+        //     4 => println!("Open in VS CODE"),
+        //     _ => self.default_mode(),
+        // }
+        Ok(())
+    }
+
+    // Отмена строки ввода: заполненный заранее текст не должен достаться следующему запросу
+    pub fn cancel_input(&mut self) {
+        self.input_buffer.clear();
+        self.default_input_mode();
+        self.mode = match self.menu_action.take() {
+            Some(MenuAction::RenameBookmark) => Mode::Bookmarks,
+            _ => Mode::Normal,
+        };
+    }
+
+    pub fn handle_menu_action(&mut self) -> io::Result<()> {
+        if let Some(action) = self.menu_action.take() {
+            match action {
+                MenuAction::CreateFile => self.create_file()?,
+                MenuAction::CreateDir => self.create_dir()?,
+                MenuAction::Rename => self.rename_selected()?,
+                MenuAction::Link(kind) => self.create_link(kind)?,
+                MenuAction::SelectGlob => self.mark_by_glob()?,
+                MenuAction::IgnorePatterns => self.set_ignore_patterns()?,
+                MenuAction::RenameBookmark => self.rename_bookmark()?,
+                MenuAction::ImportHistory => self.import_history()?,
+                MenuAction::CopyTo => self.transfer_to(false)?,
+                MenuAction::MoveTo => self.transfer_to(true)?,
+                _ => {}
+            }
+        };
+        self.default_input_mode();
+        Ok(())
+    }
+
+    // Затронутую папку может показывать любая панель любой вкладки, поэтому перечитываются все.
+    // Ошибка одной панели не мешает остальным и не делает удачное действие неудачным
+    fn update_file_list(&mut self) {
+        for index in 0..self.tabs.len() {
+            self.refresh_tab(index);
+        }
+    }
+
+    fn refresh_tab(&mut self, index: usize) {
+        let mut failed = None;
+        for panel in self.tabs[index].panels_mut() {
+            if let Err(err) = panel.refresh(&mut self.visibility) {
+                failed.get_or_insert((panel.get_current_dir().clone(), err));
+            }
+        }
+        if let Some((dir, err)) = failed {
+            self.warn(format!(
+                "Вкладка {}: не удалось перечитать {}: {}",
+                index + 1,
+                dir.display(),
+                err
+            ));
+        }
+    }
+
+    fn create_file(&mut self) -> io::Result<()> {
+        let file_name = self.input_buffer.trim();
+        if !file_name.is_empty() {
+            let file_path = self.get_current_dir().join(file_name);
+            File::create(&file_path)?;
+            self.journal.record(Change::Create {
+                path: file_path,
+                dir: false,
+            });
+            self.update_file_list();
+        }
+        self.input_buffer.clear();
+        Ok(())
+    }
+
+    fn create_dir(&mut self) -> io::Result<()> {
+        let dir_name = self.input_buffer.trim();
+        if !dir_name.is_empty() {
+            let dir_path = self.get_current_dir().join(dir_name);
+            fs::create_dir(&dir_path)?;
+            self.journal.record(Change::Create {
+                path: dir_path,
+                dir: true,
+            });
+            self.update_file_list();
+        }
+        self.input_buffer.clear();
+        Ok(())
+    }
+
+    fn rename_selected(&mut self) -> io::Result<()> {
+        let buffer = std::mem::take(&mut self.input_buffer);
+        let new_name = buffer.trim();
+        let Some(path) = self.get_selected_path() else {
+            return Ok(());
+        };
+        let new_path = path.with_file_name(new_name);
+        if new_name.is_empty() || new_path == path {
+            return Ok(());
+        }
+        // занятое имя не затирается: такое переименование нельзя было бы отменить
+        file_ops::rename_noreplace(&path, &new_path)?;
+        self.journal.record(Change::Rename(vec![(path, new_path)]));
+        self.update_file_list();
+        Ok(())
+    }
+
+    // Отмеченные элементы (или вся папка) переименовываются в $EDITOR, план показывается до выполнения
+    pub fn bulk_rename(&mut self) -> io::Result<()> {
+        let panel = self.get_panel();
+        let marked = panel.get_marked();
+        let paths: Vec<PathBuf> = if marked.is_empty() {
+            panel.get_files().clone()
+        } else {
+            panel
+                .get_files()
+                .iter()
+                .filter(|path| marked.contains(*path))
+                .cloned()
+                .collect()
+        };
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        // редактор рисовал поверх экрана, его надо перерисовать целиком
+        self.redraw = true;
+        let lines = rename::edit_names(&paths)?;
+        let renames = rename::pair_names(&paths, &lines)?;
+        if renames.is_empty() {
+            self.info(String::from("Имена не изменились"));
+            return Ok(());
+        }
+        let steps = rename::plan(renames.clone())?;
+
+        let mut lines = vec![format!("Переименований: {}", renames.len())];
+        let cycles = steps.len() - renames.len();
+        if cycles > 0 {
+            lines[0] += &format!(", через временное имя: {}", cycles);
+        }
+        lines.extend(renames.iter().take(RENAME_PREVIEW_LINES).map(|(from, to)| {
+            format!(
+                "  {} → {}",
+                from.file_name().unwrap_or_default().to_string_lossy(),
+                to.file_name().unwrap_or_default().to_string_lossy()
+            )
+        }));
+        if renames.len() > RENAME_PREVIEW_LINES {
+            lines.push(format!(
+                "  ...и ещё {}",
+                renames.len() - RENAME_PREVIEW_LINES
+            ));
+        }
+        self.ask(
+            "Переименовать?",
+            lines,
+            ConfirmAction::Rename(steps),
+            Mode::Normal,
+        );
+        Ok(())
+    }
+
+    // Выполненная часть попадает в журнал и при ошибке, чтобы её можно было отменить
+    fn apply_renames(&mut self, steps: Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
+        let (done, result) = rename::apply(steps);
+        if !done.is_empty() {
+            self.journal.record(Change::Rename(done));
+        }
+        self.clear_marks();
+        self.update_file_list();
+        result
+    }
+
+    pub fn take_redraw(&mut self) -> bool {
+        std::mem::take(&mut self.redraw)
+    }
+
+    // Имя ссылки по умолчанию — свободный вариант имени выбранного элемента
+    fn link_prompt(&mut self, action: MenuAction) {
+        match self.get_panel().get_selected_path() {
+            Some(path) => {
+                let name = file_ops::unique_name(path, &[]);
+                self.input_buffer = name
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                self.input_mode();
+                self.menu_action = Some(action);
+            }
+            None => self.default_input_mode(),
+        }
+    }
+
+    fn create_link(&mut self, kind: LinkKind) -> io::Result<()> {
+        let name = std::mem::take(&mut self.input_buffer);
+        let name = name.trim();
+        if let Some(target) = self.get_selected_path() {
+            if !name.is_empty() {
+                let link = self.get_current_dir().join(name);
+                file_ops::create_link(&target, &link, kind)?;
+                self.journal.record(Change::Link {
+                    link: link.clone(),
+                    target,
+                    kind,
+                });
+                self.update_file_list();
+                self.panel_mut().focus(&link);
+            }
+        }
+        Ok(())
+    }
+
+    // Переходит туда, куда на самом деле указывает ссылка
+    pub fn jump_to_link_target(&mut self) -> io::Result<()> {
+        let Some(path) = self.get_selected_path() else {
+            return Ok(());
+        };
+        if !path.is_symlink() {
+            self.warn(String::from("Это не ссылка"));
+            return Ok(());
+        }
+        let target = file_ops::link_target(&path)?;
+        if target.is_dir() {
+            self.load_dir(target)?;
+        } else if file_ops::exists(&target) {
+            self.select_path(&target)?;
+        } else {
+            // битая ссылка: хотя бы в папку, где должна быть цель
+            if let Some(parent) = target.parent().filter(|parent| parent.is_dir()) {
+                self.load_dir(parent.to_path_buf())?;
+            }
+            self.warn(format!("Цель не существует: {}", target.display()));
+        }
+        Ok(())
+    }
+
+    pub fn delete_permanently_prompt(&mut self) {
+        let targets = self.get_targets();
+        if !targets.is_empty() {
+            let lines = Self::describe(&targets);
+            let measured = targets.clone();
+            self.ask(
+                "Удалить навсегда? Восстановить будет нельзя",
+                lines,
+                ConfirmAction::Delete(targets),
+                Mode::Normal,
+            );
+            self.measure_confirm(&measured);
+        }
+    }
+
+    fn delete_paths(&mut self, targets: Vec<PathBuf>) {
+        let title = format!("Удаление {}", targets.len());
+        self.jobs.submit(title, Operation::Delete(targets));
+        self.clear_marks();
+    }
+
+    // Confirm
+
+    fn ask(&mut self, title: &str, lines: Vec<String>, action: ConfirmAction, back: Mode) {
+        self.confirm = Some(Confirm {
+            title: title.to_string(),
+            lines,
+            action,
+            back,
+            yes: false,
+            measure: None,
+        });
+        self.default_input_mode();
+        self.mode = Mode::Confirm;
+    }
+
+    // Объём считается в фоне, окно подтверждения открывается сразу
+    fn measure_confirm(&mut self, paths: &[PathBuf]) {
+        if let Some(confirm) = &mut self.confirm {
+            confirm.measure = Some(Measure::spawn(paths));
+        }
+    }
+
+    fn summary(count: usize, size: Option<(u64, u64)>) -> String {
+        match size {
+            Some((bytes, files)) => format!(
+                "Элементов: {}, файлов: {}, объём: {}",
+                count,
+                files,
+                format_size(bytes)
+            ),
+            None => format!("Элементов: {}, файлов и объём: вычисляется…", count),
+        }
+    }
+
+    // Что затронет операция: число элементов, файлов, объём и первые имена
+    fn describe(paths: &[PathBuf]) -> Vec<String> {
+        let mut lines = vec![Self::summary(paths.len(), None)];
+        lines.extend(
+            paths
+                .iter()
+                .take(CONFIRM_MAX_NAMES)
+                .map(|path| format!("  {}", path.display())),
+        );
+        if paths.len() > CONFIRM_MAX_NAMES {
+            lines.push(format!("  ...и ещё {}", paths.len() - CONFIRM_MAX_NAMES));
+        }
+        lines
+    }
+
+    pub fn confirm_toggle(&mut self) {
+        if let Some(confirm) = &mut self.confirm {
+            confirm.yes = !confirm.yes;
+        }
+    }
+
+    pub fn confirm_selected(&mut self) -> io::Result<()> {
+        match &self.confirm {
+            Some(confirm) if confirm.yes => self.confirm_yes(),
+            _ => {
+                self.confirm_no();
+                Ok(())
+            }
+        }
+    }
+
+    pub fn confirm_yes(&mut self) -> io::Result<()> {
+        let Some(confirm) = self.confirm.take() else {
+            return Ok(());
+        };
+        self.mode = confirm.back;
+        match confirm.action {
+            ConfirmAction::Trash(targets) => self.trash_paths(&targets)?,
+            ConfirmAction::Delete(targets) => self.delete_paths(targets),
+            ConfirmAction::Purge(index) => self.purge_entry(index),
+            ConfirmAction::Rename(steps) => self.apply_renames(steps)?,
+            ConfirmAction::Resolve(choice, apply_to_all) => {
+                self.resolve_conflict(choice, apply_to_all)?
+            }
+        }
+        Ok(())
+    }
+
+    pub fn confirm_no(&mut self) {
+        if let Some(confirm) = self.confirm.take() {
+            self.mode = confirm.back;
+        }
+    }
+
+    // Journal
+
+    pub fn get_journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    pub fn history_mode(&mut self) {
+        self.mode = Mode::History;
+    }
+
+    pub fn undo(&mut self) -> io::Result<()> {
+        let result = self.journal.undo(&mut self.jobs);
+        self.update_file_list();
+        match result? {
+            Some(description) => self.info(format!("Отменено: {}", description)),
+            None => self.warn(String::from("Нечего отменять")),
+        }
+        Ok(())
+    }
+
+    pub fn redo(&mut self) -> io::Result<()> {
+        let result = self.journal.redo(&mut self.jobs);
+        self.update_file_list();
+        match result? {
+            Some(description) => self.info(format!("Повторено: {}", description)),
+            None => self.warn(String::from("Нечего повторять")),
+        }
+        Ok(())
+    }
+
+    // Trash
+
+    pub fn trash_selected(&mut self) {
+        let targets = self.get_targets();
+        if !targets.is_empty() {
+            let lines = Self::describe(&targets);
+            let measured = targets.clone();
+            self.ask(
+                "Переместить в корзину?",
+                lines,
+                ConfirmAction::Trash(targets),
+                Mode::Normal,
+            );
+            self.measure_confirm(&measured);
+        }
+    }
+
+    fn trash_paths(&mut self, targets: &[PathBuf]) -> io::Result<()> {
+        self.clear_marks();
+        let mut entries = Vec::new();
+        let result = targets
+            .iter()
+            .try_for_each(|path| trash::trash(path).map(|entry| entries.push(entry)));
+        // в журнал попадает то, что успело уйти в корзину, даже при ошибке
+        if !entries.is_empty() {
+            self.info(format!("В корзину: {}", entries.len()));
+            self.journal.record(Change::Trash(entries));
+        }
+        self.update_file_list();
+        result
+    }
+
+    pub fn trash_mode(&mut self) {
+        self.trash_entries = trash::list();
+        self.trash_selected = 0;
+        self.mode = Mode::Trash;
+    }
+
+    pub fn trash_up(&mut self) {
+        if self.trash_selected > 0 {
+            self.trash_selected -= 1;
+        } else {
+            self.trash_selected = self.trash_entries.len().saturating_sub(1);
+        }
+    }
+
+    pub fn trash_down(&mut self) {
+        if self.trash_selected + 1 < self.trash_entries.len() {
+            self.trash_selected += 1;
+        } else {
+            self.trash_selected = 0;
+        }
+    }
+
+    pub fn restore_from_trash(&mut self) -> io::Result<()> {
+        if let Some(entry) = self.trash_entries.get(self.trash_selected) {
+            trash::restore(entry)?;
+            let entry = self.trash_entries.remove(self.trash_selected);
+            self.info(format!("Восстановлено: {}", entry.original.display()));
+            self.fix_trash_selected();
+            self.update_file_list();
+        }
+        Ok(())
+    }
+
+    pub fn purge_from_trash(&mut self) {
+        if let Some(entry) = self.trash_entries.get(self.trash_selected) {
+            let measured = [entry.file.clone()];
+            let mut lines = Self::describe(&measured);
+            lines[1] = format!("  {}", entry.original.display());
+            self.ask(
+                "Удалить из корзины навсегда?",
+                lines,
+                ConfirmAction::Purge(self.trash_selected),
+                Mode::Trash,
+            );
+            self.measure_confirm(&measured);
+        }
+    }
+
+    fn purge_entry(&mut self, index: usize) {
+        if index < self.trash_entries.len() {
+            let entry = self.trash_entries.remove(index);
+            let title = format!("Очистка {}", entry.original.display());
+            self.jobs
+                .submit(title, Operation::Delete(vec![entry.file, entry.info]));
+            self.fix_trash_selected();
+        }
+    }
+
+    fn fix_trash_selected(&mut self) {
+        if self.trash_selected >= self.trash_entries.len() {
+            self.trash_selected = self.trash_entries.len().saturating_sub(1);
+        }
+    }
+
+    // Clipboard
+
+    pub fn yank_selected(&mut self, cut: bool) {
+        let paths = self.get_targets();
+        if !paths.is_empty() {
+            let kind = if cut {
+                "Вырезано"
+            } else {
+                "Скопировано"
+            };
+            self.info(format!("{}: {}", kind, paths.len()));
+            self.clipboard = Some(Clipboard { paths, cut });
+        }
+        self.clear_marks();
+        self.default_mode();
+    }
+
+    pub fn paste(&mut self) -> io::Result<()> {
+        if let Some(clipboard) = &self.clipboard {
+            self.paste = Some(Paste {
+                queue: clipboard.paths.iter().cloned().collect(),
+                planned: Vec::new(),
+                dest: self.get_current_dir().clone(),
+                cut: clipboard.cut,
+                policy: None,
+            });
+        }
+        self.continue_paste()
+    }
+
+    // Вырезанное остаётся в буфере, пока задача перемещения его действительно не перенесёт:
+    // после отмены вставки или ошибки его можно вставить снова
+    fn forget_moved(&mut self, transfers: &[Transfer]) {
+        let Some(clipboard) = self.clipboard.as_mut().filter(|clipboard| clipboard.cut) else {
+            return;
+        };
+        let moved: HashSet<&PathBuf> = transfers.iter().map(|transfer| &transfer.source).collect();
+        clipboard.paths.retain(|path| !moved.contains(path));
+        if clipboard.paths.is_empty() {
+            self.clipboard = None;
+        }
+    }
+
+    // Раскладывает элементы буфера по целевым путям, останавливаясь на конфликте имён
+    fn continue_paste(&mut self) -> io::Result<()> {
+        while let Some(paste) = &mut self.paste {
+            let Some(source) = paste.queue.front().cloned() else {
+                break;
+            };
+            let Some(name) = source.file_name() else {
+                paste.queue.pop_front();
+                continue;
+            };
+            let target = paste.dest.join(name);
+
+            if paste.dest.starts_with(&source) && source != target {
+                self.paste = None;
+                self.default_mode();
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Нельзя вставить папку в саму себя",
+                ));
+            }
+
+            let choice = if source == target {
+                // копия в ту же папку получает новое имя, перемещение ничего не меняет
+                if paste.cut {
+                    ConflictChoice::Skip
+                } else {
+                    ConflictChoice::Rename
+                }
+            } else if paste.is_taken(&target) {
+                match paste.policy {
+                    Some(policy) => policy,
+                    None => {
+                        self.conflict_selected = 0;
+                        self.mode = Mode::Conflict;
+                        return Ok(());
+                    }
+                }
+            } else {
+                ConflictChoice::Overwrite
+            };
+
+            paste.queue.pop_front();
+            paste.plan(source, target, choice);
+        }
+
+        if let Some(paste) = self.paste.take() {
+            let title = format!(
+                "{} {} → {}",
+                if paste.cut {
+                    "Перемещение"
+                } else {
+                    "Копирование"
+                },
+                paste.planned.len(),
+                paste.dest.display()
+            );
+            if !paste.planned.is_empty() {
+                let (operation, change): (Operation, Recorder) = if paste.cut {
+                    (Operation::Move(paste.planned), Change::Move)
+                } else {
+                    (Operation::Copy(paste.planned), Change::Copy)
+                };
+                self.jobs
+                    .submit_recorded(title, operation, Step::Do, change);
+            }
+        }
+        self.default_mode();
+        Ok(())
+    }
+
+    // Папка назначения по умолчанию — папка другой панели, её можно исправить
+    pub fn transfer_prompt(&mut self, cut: bool) {
+        if self.get_targets().is_empty() {
+            self.default_mode();
+            return;
+        }
+        self.input_buffer = self
+            .get_other_panel()
+            .get_current_dir()
+            .display()
+            .to_string();
+        self.menu_mode();
+        self.input_mode();
+        self.menu_action = Some(if cut {
+            MenuAction::MoveTo
+        } else {
+            MenuAction::CopyTo
+        });
+    }
+
+    fn transfer_to(&mut self, cut: bool) -> io::Result<()> {
+        let buffer = std::mem::take(&mut self.input_buffer);
+        self.default_mode();
+        let dest = match buffer.trim().strip_prefix('#') {
+            Some(number) if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) => {
+                self.get_tab_dir(number.parse().unwrap_or(0))?
+            }
+            _ => self.get_current_dir().join(buffer.trim()),
+        };
+        if !dest.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Нет такой папки: {}", dest.display()),
+            ));
+        }
+        let targets = self.get_targets();
+        self.clear_marks();
+        self.paste = Some(Paste {
+            queue: targets.into_iter().collect(),
+            planned: Vec::new(),
+            dest,
+            cut,
+            policy: None,
+        });
+        self.continue_paste()
+    }
+
+    pub fn get_conflict_name(&self) -> Option<String> {
+        let paste = self.paste.as_ref()?;
+        let name = paste.queue.front()?.file_name()?;
+        Some(name.to_string_lossy().into_owned())
+    }
+
+    pub fn conflict_up(&mut self) {
+        if self.conflict_selected > 0 {
+            self.conflict_selected -= 1
+        } else {
+            self.conflict_selected = CONFLICT_MENU.len() - 1;
+        }
+    }
+
+    pub fn conflict_down(&mut self) {
+        if self.conflict_selected < CONFLICT_MENU.len() - 1 {
+            self.conflict_selected += 1;
+        } else {
+            self.conflict_selected = 0;
+        }
+    }
+
+    pub fn select_from_conflict(&mut self) -> io::Result<()> {
+        let Some((choice, apply_to_all)) = CONFLICT_MENU[self.conflict_selected].0 else {
+            self.cancel_paste();
+            return Ok(());
         };
-        self.update_file_list()?;
+        if choice != ConflictChoice::Overwrite {
+            return self.resolve_conflict(choice, apply_to_all);
+        }
+
+        // перезапись подтверждается со списком того, что будет потеряно
+        let Some(paste) = &self.paste else {
+            return Ok(());
+        };
+        let conflicts: Vec<PathBuf> = paste
+            .queue
+            .iter()
+            .take(if apply_to_all { paste.queue.len() } else { 1 })
+            .filter_map(|source| Some(paste.dest.join(source.file_name()?)))
+            .filter(|target| !paste.queue.contains(target) && paste.is_taken(target))
+            .collect();
+        let lines = Self::describe(&conflicts);
+        self.ask(
+            "Перезаписать существующие элементы?",
+            lines,
+            ConfirmAction::Resolve(choice, apply_to_all),
+            Mode::Conflict,
+        );
+        self.measure_confirm(&conflicts);
         Ok(())
     }
 
+    fn resolve_conflict(&mut self, choice: ConflictChoice, apply_to_all: bool) -> io::Result<()> {
+        let Some(paste) = &mut self.paste else {
+            self.default_mode();
+            return Ok(());
+        };
+
+        if apply_to_all {
+            paste.policy = Some(choice);
+        }
+        if let Some(source) = paste.queue.pop_front() {
+            if let Some(name) = source.file_name() {
+                let target = paste.dest.join(name);
+                paste.plan(source, target, choice);
+            }
+        }
+        self.continue_paste()
+    }
+
+    pub fn cancel_paste(&mut self) {
+        self.paste = None;
+        self.default_mode();
+    }
+
     // open files in editor
 
-    fn opn_in_editor(file_path: &PathBuf, editor: &str) -> Result<(), io::Error> {
+    fn opn_in_editor(file_paths: &[PathBuf], editor: &str) -> Result<(), io::Error> {
         let mut binding = Command::new(editor);
         match editor {
-            "nano" | "vim" => binding.arg(file_path),
+            "nano" | "vim" => binding.args(file_paths),
             _ => binding
-                .arg(file_path)
+                .args(file_paths)
                 .stdout(Stdio::null())
                 .stderr(Stdio::null()),
         };
@@ -489,7 +2427,7 @@ impl FileManager {
     }
 
     fn get_ide_list() -> Vec<&'static str> {
-         vec![
+        vec![
             // Универсальные IDE
             "code",      // Visual Studio Code
             "codium",    // VSCodium (альтернатива VSCode)
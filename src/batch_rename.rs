@@ -0,0 +1,317 @@
+use crate::file_ops;
+use regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// Подстановки в шаблоне замены: {n}, {n:03}, {name}, {ext}
+const TOKENS: &str = r"\{n(?::0?(\d+))?\}|\{name\}|(\.?)\{ext\}";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Case {
+    Keep,
+    Lower,
+    Upper,
+    Title,
+}
+
+impl Case {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Case::Keep => "как есть",
+            Case::Lower => "строчные",
+            Case::Upper => "ПРОПИСНЫЕ",
+            Case::Title => "Каждое Слово С Заглавной",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Case::Keep => Case::Lower,
+            Case::Lower => Case::Upper,
+            Case::Upper => Case::Title,
+            Case::Title => Case::Keep,
+        }
+    }
+
+    fn apply(&self, name: &str) -> String {
+        match self {
+            Case::Keep => name.to_string(),
+            Case::Lower => name.to_lowercase(),
+            Case::Upper => name.to_uppercase(),
+            Case::Title => {
+                let mut result = String::with_capacity(name.len());
+                let mut word_start = true;
+                for c in name.chars() {
+                    if word_start {
+                        result.extend(c.to_uppercase());
+                    } else {
+                        result.extend(c.to_lowercase());
+                    }
+                    word_start = !c.is_alphanumeric();
+                }
+                result
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Find,
+    Replace,
+    Case,
+    Extension,
+}
+
+const FIELDS: [Field; 4] = [Field::Find, Field::Replace, Field::Case, Field::Extension];
+
+// Строка предпросмотра: старое и новое имя, конфликт с другим именем
+pub struct Preview {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub collision: bool,
+}
+
+// Окно переименования по шаблону для отмеченных элементов
+pub struct BatchRenameDialog {
+    targets: Vec<PathBuf>,
+    find: String,
+    replace: String,
+    case: Case,
+    extension: String,
+    selected: usize,
+    preview: Vec<Preview>,
+    error: Option<String>,
+}
+
+impl BatchRenameDialog {
+    pub fn new(targets: Vec<PathBuf>) -> Self {
+        let mut dialog = Self {
+            targets,
+            find: String::new(),
+            replace: String::new(),
+            case: Case::Keep,
+            extension: String::new(),
+            selected: 0,
+            preview: Vec::new(),
+            error: None,
+        };
+        dialog.update();
+        dialog
+    }
+
+    // Getters
+    pub fn get_find(&self) -> &String {
+        &self.find
+    }
+
+    pub fn get_replace(&self) -> &String {
+        &self.replace
+    }
+
+    pub fn get_case(&self) -> Case {
+        self.case
+    }
+
+    pub fn get_extension(&self) -> &String {
+        &self.extension
+    }
+
+    pub fn get_preview(&self) -> &Vec<Preview> {
+        &self.preview
+    }
+
+    pub fn get_error(&self) -> &Option<String> {
+        &self.error
+    }
+
+    pub fn get_field(&self) -> Field {
+        FIELDS[self.selected]
+    }
+
+    pub fn get_collision_count(&self) -> usize {
+        self.preview.iter().filter(|row| row.collision).count()
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % FIELDS.len();
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = (self.selected + FIELDS.len() - 1) % FIELDS.len();
+    }
+
+    // Пробел переключает регистр, в текстовых полях это обычный символ
+    pub fn toggle(&mut self) {
+        if self.get_field() == Field::Case {
+            self.case = self.case.next();
+            self.update();
+        } else {
+            self.push(' ');
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        match self.get_field() {
+            Field::Find => self.find.push(c),
+            Field::Replace => self.replace.push(c),
+            Field::Extension => self.extension.push(c),
+            Field::Case => return,
+        }
+        self.update();
+    }
+
+    pub fn pop(&mut self) {
+        match self.get_field() {
+            Field::Find => self.find.pop(),
+            Field::Replace => self.replace.pop(),
+            Field::Extension => self.extension.pop(),
+            Field::Case => return,
+        };
+        self.update();
+    }
+
+    // Пары для выполнения; None, если шаблон с ошибкой или имена совпадают
+    pub fn renames(&self) -> Option<Vec<(PathBuf, PathBuf)>> {
+        if self.error.is_some() || self.get_collision_count() > 0 {
+            return None;
+        }
+        Some(
+            self.preview
+                .iter()
+                .filter(|row| row.from != row.to)
+                .map(|row| (row.from.clone(), row.to.clone()))
+                .collect(),
+        )
+    }
+
+    // Предпросмотр пересчитывается после каждого изменения полей
+    fn update(&mut self) {
+        self.error = None;
+        let find = if self.find.is_empty() {
+            // без шаблона поиска замена подставляется вместо всего имени
+            if self.replace.is_empty() {
+                None
+            } else {
+                Some(String::from("^.*$"))
+            }
+        } else {
+            Some(self.find.clone())
+        };
+        let regex = match find.map(|find| Regex::new(&find)).transpose() {
+            Ok(regex) => regex,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                None
+            }
+        };
+        let tokens = Regex::new(TOKENS).unwrap();
+
+        let names: Vec<String> = self
+            .targets
+            .iter()
+            .enumerate()
+            .map(|(index, path)| self.new_name(path, index + 1, regex.as_ref(), &tokens))
+            .collect();
+
+        let sources: HashSet<&PathBuf> = self
+            .targets
+            .iter()
+            .zip(&names)
+            .filter(|(path, name)| name_of(path) != **name)
+            .map(|(path, _)| path)
+            .collect();
+        let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+        let mut invalid = None;
+        let rows: Vec<(PathBuf, PathBuf)> = self
+            .targets
+            .iter()
+            .zip(names)
+            .map(|(path, name)| {
+                let to = if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+                    invalid.get_or_insert(format!("недопустимое имя для {}", name_of(path)));
+                    path.clone()
+                } else {
+                    path.with_file_name(name)
+                };
+                *counts.entry(to.clone()).or_default() += 1;
+                (path.clone(), to)
+            })
+            .collect();
+        if self.error.is_none() {
+            self.error = invalid;
+        }
+
+        self.preview = rows
+            .into_iter()
+            .map(|(from, to)| {
+                // имя занято другим элементом, который не уходит со своего места
+                let taken = from != to && file_ops::exists(&to) && !sources.contains(&to);
+                let collision = counts[&to] > 1 || taken;
+                Preview {
+                    from,
+                    to,
+                    collision,
+                }
+            })
+            .collect();
+    }
+
+    fn new_name(
+        &self,
+        path: &Path,
+        number: usize,
+        regex: Option<&Regex>,
+        tokens: &Regex,
+    ) -> String {
+        let name = name_of(path);
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut name = match regex {
+            Some(regex) => {
+                // $ в подставленных значениях не должен читаться как номер группы
+                let replacement = tokens.replace_all(&self.replace, |caps: &Captures| {
+                    let value = if let Some(dot) = caps.get(2) {
+                        if extension.is_empty() {
+                            String::new()
+                        } else {
+                            format!("{}{}", dot.as_str(), extension)
+                        }
+                    } else if caps[0].starts_with("{name") {
+                        stem.clone()
+                    } else {
+                        let width = caps.get(1).map_or(0, |w| w.as_str().parse().unwrap_or(0));
+                        format!("{:0width$}", number, width = width)
+                    };
+                    value.replace('$', "$$")
+                });
+                regex.replace_all(&name, replacement.as_ref()).into_owned()
+            }
+            None => name,
+        };
+
+        name = self.case.apply(&name);
+        let extension = self.extension.trim().trim_start_matches('.');
+        if !extension.is_empty() {
+            let stem = Path::new(&name)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            name = format!("{}.{}", stem, extension);
+        }
+        name
+    }
+}
+
+fn name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,126 @@
+use crate::config;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Файл закладок в ~/.config/cli_file_manager
+const BOOKMARKS_FILE: &str = "bookmarks";
+
+pub struct Bookmark {
+    pub key: char,
+    pub name: String,
+    pub path: PathBuf,
+}
+
+// Закладки на папки по буквам, как метки в vim
+#[derive(Default)]
+pub struct Bookmarks {
+    items: Vec<Bookmark>,
+    selected: usize,
+}
+
+impl Bookmarks {
+    // Строка файла: буква, имя и путь через табуляцию; испорченные строки пропускаются
+    pub fn load() -> Self {
+        let lines = config::config_path(BOOKMARKS_FILE)
+            .map(|path| config::read_lines(&path))
+            .unwrap_or_default();
+        let items = lines
+            .iter()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let key = fields.next()?.chars().next()?;
+                let name = fields.next()?.to_string();
+                let path = PathBuf::from(fields.next()?);
+                Some(Bookmark { key, name, path })
+            })
+            .collect();
+        Self { items, selected: 0 }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let path = config::config_path(BOOKMARKS_FILE)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Не задан HOME"))?;
+        let lines: Vec<String> = self
+            .items
+            .iter()
+            .map(|bookmark| {
+                format!(
+                    "{}\t{}\t{}",
+                    bookmark.key,
+                    bookmark.name,
+                    bookmark.path.display()
+                )
+            })
+            .collect();
+        config::write_lines(&path, &lines)
+    }
+
+    // Getters
+    pub fn get_items(&self) -> &Vec<Bookmark> {
+        &self.items
+    }
+
+    pub fn get_selected(&self) -> &usize {
+        &self.selected
+    }
+
+    pub fn get(&self, key: char) -> Option<&Bookmark> {
+        self.items.iter().find(|bookmark| bookmark.key == key)
+    }
+
+    pub fn get_selected_bookmark(&self) -> Option<&Bookmark> {
+        self.items.get(self.selected)
+    }
+
+    // Буква переназначается на новую папку, имя по умолчанию — имя папки
+    pub fn set(&mut self, key: char, path: &Path) -> io::Result<()> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        self.items.retain(|bookmark| bookmark.key != key);
+        self.items.push(Bookmark {
+            key,
+            name,
+            path: path.to_path_buf(),
+        });
+        self.items.sort_by_key(|bookmark| bookmark.key);
+        self.save()
+    }
+
+    pub fn rename_selected(&mut self, name: &str) -> io::Result<()> {
+        if let Some(bookmark) = self.items.get_mut(self.selected) {
+            // табуляция разделяет поля в файле
+            bookmark.name = name.replace('\t', " ");
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn remove_selected(&mut self) -> io::Result<()> {
+        if self.selected < self.items.len() {
+            self.items.remove(self.selected);
+            if self.selected >= self.items.len() {
+                self.selected = self.items.len().saturating_sub(1);
+            }
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        } else {
+            self.selected = self.items.len().saturating_sub(1);
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.items.len() {
+            self.selected += 1;
+        } else {
+            self.selected = 0;
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const APP_DIR: &str = "cli_file_manager";
+
+// Каталог из переменной XDG, а если она не задана — из домашней папки
+pub fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+}
+
+pub fn config_path(name: &str) -> Option<PathBuf> {
+    Some(
+        xdg_dir("XDG_CONFIG_HOME", ".config")?
+            .join(APP_DIR)
+            .join(name),
+    )
+}
+
+pub fn data_path(name: &str) -> Option<PathBuf> {
+    Some(
+        xdg_dir("XDG_DATA_HOME", ".local/share")?
+            .join(APP_DIR)
+            .join(name),
+    )
+}
+
+// Строки файла без пустых и комментариев; отсутствующий файл — пустой список
+pub fn read_lines(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+pub fn write_lines(path: &Path, lines: &[String]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(path, content)
+}
@@ -0,0 +1,19 @@
+use std::io;
+
+pub enum AppError {
+    // Пользователь попросил выйти
+    Quit,
+    // Дальше работать нельзя: сломался терминал или не удалось запуститься
+    Fatal(io::Error),
+    // Не удалось одно действие, программа показывает ошибку и продолжает работу
+    Recoverable(io::Error),
+}
+
+pub type AppResult<T = ()> = Result<T, AppError>;
+
+// Ошибки действий с файлами по умолчанию не мешают продолжить работу
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        AppError::Recoverable(err)
+    }
+}
@@ -0,0 +1,390 @@
+use crate::jobs::Progress;
+use filetime::FileTime;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::{lchown, symlink, MetadataExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+// Копирует файл, папку (рекурсивно) или symlink, сохраняя права и время изменения
+pub fn copy_path(from: &Path, to: &Path, progress: &Progress) -> io::Result<()> {
+    progress.check()?;
+    let metadata = fs::symlink_metadata(from)?;
+    let file_type = metadata.file_type();
+
+    if file_type.is_symlink() {
+        symlink(fs::read_link(from)?, to)?;
+        progress.add(0, 1);
+        return Ok(());
+    }
+
+    if file_type.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &to.join(entry.file_name()), progress)?;
+        }
+    } else if file_type.is_file() {
+        copy_file(from, to, progress)?;
+    } else {
+        copy_special(to, &metadata)?;
+        progress.add(0, 1);
+    }
+
+    fs::set_permissions(to, metadata.permissions())?;
+    filetime::set_file_times(
+        to,
+        FileTime::from_last_access_time(&metadata),
+        FileTime::from_last_modification_time(&metadata),
+    )?;
+    Ok(())
+}
+
+// Копирует содержимое файла кусками, чтобы показывать прогресс и успевать отменить.
+// Существующий файл не перезаписывается: его заранее убирает тот, кто разрешил замену
+fn copy_file(from: &Path, to: &Path, progress: &Progress) -> io::Result<()> {
+    let mut reader = File::open(from)?;
+    let mut writer = OpenOptions::new().write(true).create_new(true).open(to)?;
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+
+    loop {
+        progress.check()?;
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        progress.add(read as u64, 0);
+    }
+    progress.add(0, 1);
+    Ok(())
+}
+
+// Канал и устройство создаются заново: чтение из них заблокировало бы задачу
+// или не закончилось бы никогда. Сокет без своего процесса бесполезен
+fn copy_special(to: &Path, metadata: &fs::Metadata) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::FileTypeExt;
+
+    let file_type = metadata.file_type();
+    if file_type.is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Сокет нельзя скопировать",
+        ));
+    }
+    let path = CString::new(to.as_os_str().as_bytes())?;
+    let code = if file_type.is_fifo() {
+        unsafe { libc::mkfifo(path.as_ptr(), metadata.mode() as libc::mode_t) }
+    } else {
+        unsafe {
+            libc::mknod(
+                path.as_ptr(),
+                metadata.mode() as libc::mode_t,
+                metadata.rdev() as libc::dev_t,
+            )
+        }
+    };
+    if code != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// Перемещает путь; между файловыми системами — копированием с удалением
+pub fn move_path(from: &Path, to: &Path, size: (u64, u64), progress: &Progress) -> io::Result<()> {
+    progress.check()?;
+    match rename_noreplace(from, to) {
+        Ok(()) => {
+            progress.add(size.0, size.1);
+            return Ok(());
+        }
+        // копировать имеет смысл только при переносе на другой раздел,
+        // остальные ошибки (нет прав, папка в саму себя) возвращаются как есть
+        Err(err) if err.raw_os_error() != Some(libc::EXDEV) => return Err(err),
+        Err(_) => {}
+    }
+    copy_path(from, to, progress)?;
+    // источник удаляется целиком, даже если в этот момент нажали отмену
+    remove_path(from, &Progress::default())
+}
+
+pub fn remove_path(path: &Path, progress: &Progress) -> io::Result<()> {
+    progress.check()?;
+    let metadata = fs::symlink_metadata(path)?;
+
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            remove_path(&entry?.path(), progress)?;
+        }
+        fs::remove_dir(path)?;
+    } else {
+        fs::remove_file(path)?;
+        progress.add(metadata.len(), 1);
+    }
+    Ok(())
+}
+
+// Биты, которые ставятся и снимаются; остальные права каждого элемента не трогаются
+pub struct ModeChange {
+    pub set: u32,
+    pub clear: u32,
+}
+
+impl ModeChange {
+    pub fn apply(&self, mode: u32) -> u32 {
+        (mode & !self.clear) | self.set
+    }
+}
+
+pub struct Attributes {
+    pub mode: Option<ModeChange>,
+    // файлам x ставится, только если они уже были исполняемыми (как X в chmod)
+    pub exec_dirs_only: bool,
+    pub owner: Option<u32>,
+    pub group: Option<u32>,
+    pub recursive: bool,
+}
+
+// Меняет права и владельца; ошибка одного элемента не останавливает обход
+pub fn set_attributes(
+    path: &Path,
+    attributes: &Attributes,
+    progress: &Progress,
+    failed: &mut Vec<(PathBuf, io::Error)>,
+) -> io::Result<()> {
+    progress.check()?;
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            failed.push((path.to_path_buf(), err));
+            return Ok(());
+        }
+    };
+
+    if attributes.owner.is_some() || attributes.group.is_some() {
+        if let Err(err) = lchown(path, attributes.owner, attributes.group) {
+            failed.push((path.to_path_buf(), err));
+        }
+    }
+    // права у самой ссылки в Linux не меняются
+    if let Some(change) = attributes
+        .mode
+        .as_ref()
+        .filter(|_| !metadata.file_type().is_symlink())
+    {
+        let old = metadata.mode() & 0o7777;
+        let mut mode = change.apply(old);
+        if attributes.exec_dirs_only && !metadata.is_dir() && old & 0o111 == 0 {
+            mode &= !0o111;
+        }
+        if mode != old {
+            if let Err(err) = fs::set_permissions(path, fs::Permissions::from_mode(mode)) {
+                failed.push((path.to_path_buf(), err));
+            }
+        }
+    }
+
+    if metadata.is_dir() {
+        if attributes.recursive {
+            match fs::read_dir(path) {
+                Ok(entries) => {
+                    for entry in entries.filter_map(|entry| entry.ok()) {
+                        set_attributes(&entry.path(), attributes, progress, failed)?;
+                    }
+                }
+                Err(err) => failed.push((path.to_path_buf(), err)),
+            }
+        }
+    } else {
+        progress.add(metadata.len(), 1);
+    }
+    Ok(())
+}
+
+// Размер в байтах и число файлов, symlink не раскрываются
+pub fn measure(path: &Path) -> (u64, u64) {
+    measure_until(path, &AtomicBool::new(false))
+}
+
+fn measure_until(path: &Path, cancel: &AtomicBool) -> (u64, u64) {
+    if cancel.load(Ordering::Relaxed) {
+        return (0, 0);
+    }
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return (0, 0);
+    };
+    if !metadata.is_dir() {
+        let bytes = if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        };
+        return (bytes, 1);
+    }
+
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| measure_until(&entry.path(), cancel))
+        .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f))
+}
+
+// Подсчёт объёма в отдельном потоке, чтобы большое дерево не останавливало интерфейс.
+// Прерывается, когда результат больше не нужен
+pub struct Measure {
+    count: usize,
+    receiver: Receiver<(u64, u64)>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Measure {
+    pub fn spawn(paths: &[PathBuf]) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let worker_paths = paths.to_vec();
+        let worker_cancel = cancel.clone();
+        thread::spawn(move || {
+            let size = worker_paths
+                .iter()
+                .map(|path| measure_until(path, &worker_cancel))
+                .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f));
+            let _ = sender.send(size);
+        });
+
+        Self {
+            count: paths.len(),
+            receiver,
+            cancel,
+        }
+    }
+
+    pub fn get_count(&self) -> usize {
+        self.count
+    }
+
+    // Байты и файлы, когда подсчёт закончен
+    pub fn poll(&self) -> Option<(u64, u64)> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for Measure {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LinkKind {
+    Absolute,
+    Relative,
+    Hard,
+}
+
+pub fn create_link(target: &Path, link: &Path, kind: LinkKind) -> io::Result<()> {
+    match kind {
+        LinkKind::Absolute => symlink(std::path::absolute(target)?, link),
+        LinkKind::Relative => {
+            let base = std::path::absolute(link)?;
+            let base = base.parent().unwrap_or(Path::new("/"));
+            symlink(relative_path(base, &std::path::absolute(target)?), link)
+        }
+        LinkKind::Hard => fs::hard_link(target, link),
+    }
+}
+
+// Путь до to относительно папки from, оба пути абсолютные
+pub fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut relative: PathBuf = from[common..].iter().map(|_| "..").collect();
+    relative.extend(&to[common..]);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+// Конечная цель цепочки ссылок; для битой ссылки — путь, на который она указывает
+pub fn link_target(path: &Path) -> io::Result<PathBuf> {
+    fs::canonicalize(path).or_else(|_| {
+        let target = fs::read_link(path)?;
+        Ok(path.parent().unwrap_or(Path::new("/")).join(target))
+    })
+}
+
+pub fn exists(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+// rename, который не заменяет существующую цель (RENAME_NOREPLACE).
+// Где флаг не поддерживается, цель проверяется перед переименованием
+pub fn rename_noreplace(from: &Path, to: &Path) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let from_c = CString::new(from.as_os_str().as_bytes())?;
+        let to_c = CString::new(to.as_os_str().as_bytes())?;
+        let code = unsafe {
+            libc::renameat2(
+                libc::AT_FDCWD,
+                from_c.as_ptr(),
+                libc::AT_FDCWD,
+                to_c.as_ptr(),
+                libc::RENAME_NOREPLACE,
+            )
+        };
+        if code == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::EEXIST) => return Err(already_exists(to)),
+            Some(libc::EINVAL) | Some(libc::ENOSYS) => {}
+            _ => return Err(err),
+        }
+    }
+
+    if exists(to) {
+        return Err(already_exists(to));
+    }
+    fs::rename(from, to)
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} уже существует", path.display()),
+    )
+}
+
+// Подбирает свободное имя вида name_1.ext, name_2.ext, ...
+pub fn unique_name(path: &Path, taken: &[PathBuf]) -> PathBuf {
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| parent.join(format!("{}_{}{}", stem, n, extension)))
+        .find(|candidate| !exists(candidate) && !taken.contains(candidate))
+        .unwrap()
+}
@@ -0,0 +1,414 @@
+use crate::config;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// База посещённых папок в ~/.local/share/cli_file_manager
+const FRECENCY_FILE: &str = "frecency";
+// Когда сумма рангов превышает предел, все ранги уменьшаются (как _ZO_MAXAGE в zoxide)
+const MAX_AGE: f64 = 10000.0;
+const MAX_RESULTS: usize = 100;
+// Посещения копятся в памяти и пишутся на диск не чаще этого интервала и при выходе
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+pub struct Entry {
+    pub path: PathBuf,
+    pub rank: f64,
+    pub last_access: u64,
+}
+
+impl Entry {
+    // Частота посещений с поправкой на давность последнего
+    pub fn score(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_access);
+        let weight = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * weight
+    }
+}
+
+#[derive(Default)]
+pub struct Frecency {
+    entries: HashMap<PathBuf, Entry>,
+    // есть изменения, ещё не записанные в файл
+    dirty: bool,
+    saved: Option<Instant>,
+}
+
+impl Frecency {
+    // Строка файла: ранг, время последнего посещения и путь через табуляцию.
+    // Удалённые папки отбрасываются при загрузке
+    pub fn load() -> Self {
+        let lines = config::data_path(FRECENCY_FILE)
+            .map(|path| config::read_lines(&path))
+            .unwrap_or_default();
+        let mut frecency = Self::default();
+        for entry in lines.iter().filter_map(|line| parse_line(line)) {
+            frecency.add(entry.path, entry.rank, entry.last_access);
+        }
+
+        let count = frecency.entries.len();
+        frecency.prune();
+        if frecency.entries.len() != count {
+            let _ = frecency.save();
+        }
+        frecency
+    }
+
+    // Записывает накопленные посещения, если с прошлой записи прошло достаточно времени
+    pub fn save_if_due(&mut self) -> io::Result<()> {
+        if self
+            .saved
+            .is_some_and(|saved| saved.elapsed() < SAVE_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.flush()
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    fn save(&mut self) -> io::Result<()> {
+        let path = config::data_path(FRECENCY_FILE)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Не задан HOME"))?;
+        let lines: Vec<String> = self
+            .entries
+            .values()
+            .map(|entry| {
+                format!(
+                    "{}\t{}\t{}",
+                    entry.rank,
+                    entry.last_access,
+                    entry.path.display()
+                )
+            })
+            .collect();
+        config::write_lines(&path, &lines)?;
+        self.dirty = false;
+        self.saved = Some(Instant::now());
+        Ok(())
+    }
+
+    // Getters
+    pub fn get_entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+
+    // Только в памяти: файл перезаписывается в save_if_due и flush
+    pub fn visit(&mut self, path: &Path) {
+        self.add(path.to_path_buf(), 1.0, now());
+        self.age();
+        self.dirty = true;
+    }
+
+    pub fn remove(&mut self, path: &Path) -> io::Result<()> {
+        self.entries.remove(path);
+        self.save()
+    }
+
+    // Ранги и время из файла zoxide, z или autojump складываются с уже известными
+    pub fn import(&mut self, file: &Path) -> io::Result<usize> {
+        let data = fs::read(file)?;
+        let imported = if is_zoxide(file, &data) {
+            parse_zoxide(&data)?
+        } else {
+            parse_text(&String::from_utf8_lossy(&data))
+        };
+        if imported.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("В {} нет записей zoxide, z или autojump", file.display()),
+            ));
+        }
+
+        // удалённые с тех пор папки не переносятся
+        let mut count = 0;
+        for entry in imported.into_iter().filter(|entry| entry.path.is_dir()) {
+            self.add(entry.path, entry.rank, entry.last_access);
+            count += 1;
+        }
+        self.age();
+        self.save()?;
+        Ok(count)
+    }
+
+    fn add(&mut self, path: PathBuf, rank: f64, last_access: u64) {
+        let entry = self.entries.entry(path.clone()).or_insert(Entry {
+            path,
+            rank: 0.0,
+            last_access,
+        });
+        entry.rank += rank;
+        entry.last_access = entry.last_access.max(last_access);
+    }
+
+    // Старые редкие папки постепенно вытесняются частыми
+    fn age(&mut self) {
+        let total: f64 = self.entries.values().map(|entry| entry.rank).sum();
+        if total > MAX_AGE {
+            let factor = 0.9 * MAX_AGE / total;
+            for entry in self.entries.values_mut() {
+                entry.rank *= factor;
+            }
+            self.entries.retain(|_, entry| entry.rank >= 1.0);
+        }
+    }
+
+    fn prune(&mut self) {
+        self.entries.retain(|path, _| path.is_dir());
+    }
+}
+
+// Накопленное записывается при любом выходе: обычном, по ошибке и при панике,
+// когда раскрутка стека удаляет FileManager
+impl Drop for Frecency {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+// Окно перехода: нечёткий поиск по посещённым папкам
+pub struct Jump {
+    query: String,
+    entries: Vec<(PathBuf, f64)>,
+    results: Vec<usize>,
+    selected: usize,
+    matcher: SkimMatcherV2,
+}
+
+impl Jump {
+    pub fn new(frecency: &Frecency, current_dir: &Path) -> Self {
+        let now = now();
+        let entries = frecency
+            .get_entries()
+            .filter(|entry| entry.path != current_dir)
+            .map(|entry| (entry.path.clone(), entry.score(now)))
+            .collect();
+        let mut jump = Self {
+            query: String::new(),
+            entries,
+            results: Vec::new(),
+            selected: 0,
+            matcher: SkimMatcherV2::default(),
+        };
+        jump.rerank();
+        jump
+    }
+
+    // Getters
+    pub fn get_query(&self) -> &String {
+        &self.query
+    }
+
+    pub fn get_selected(&self) -> &usize {
+        &self.selected
+    }
+
+    pub fn get_results(&self) -> Vec<&(PathBuf, f64)> {
+        self.results
+            .iter()
+            .map(|index| &self.entries[*index])
+            .collect()
+    }
+
+    pub fn get_selected_path(&self) -> Option<&PathBuf> {
+        self.results
+            .get(self.selected)
+            .map(|index| &self.entries[*index].0)
+    }
+
+    pub fn add_to_query(&mut self, c: char) {
+        self.query.push(c);
+        self.rerank();
+    }
+
+    pub fn delete_from_query(&mut self) {
+        self.query.pop();
+        self.rerank();
+    }
+
+    pub fn up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        } else {
+            self.selected = self.results.len().saturating_sub(1);
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.results.len() {
+            self.selected += 1;
+        } else {
+            self.selected = 0;
+        }
+    }
+
+    // Совпадение умножается на логарифм частоты: точное совпадение важнее,
+    // а из похожих выше та папка, где бывают чаще
+    fn rerank(&mut self) {
+        let mut ranked: Vec<(f64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (path, score))| {
+                let weight = score.ln_1p() + 1.0;
+                if self.query.is_empty() {
+                    return Some((weight, index));
+                }
+                let haystack = path.to_string_lossy();
+                self.matcher
+                    .fuzzy_match(&haystack, &self.query)
+                    .map(|fuzzy| (fuzzy as f64 * weight, index))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked.truncate(MAX_RESULTS);
+        self.results = ranked.into_iter().map(|(_, index)| index).collect();
+        self.selected = 0;
+    }
+}
+
+// Файлы для импорта, которые стоит предложить первыми
+pub fn import_candidates() -> Vec<PathBuf> {
+    let data = config::xdg_dir("XDG_DATA_HOME", ".local/share");
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let zoxide = std::env::var_os("_ZO_DATA_DIR")
+        .map(PathBuf::from)
+        .or_else(|| data.as_ref().map(|data| data.join("zoxide")))
+        .map(|dir| dir.join("db.zo"));
+    let z = std::env::var_os("_Z_DATA")
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| home.join(".z")));
+    let autojump = data.map(|data| data.join("autojump").join("autojump.txt"));
+    [zoxide, z, autojump]
+        .into_iter()
+        .flatten()
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+fn parse_line(line: &str) -> Option<Entry> {
+    let mut fields = line.splitn(3, '\t');
+    let rank = fields.next()?.parse().ok()?;
+    let last_access = fields.next()?.parse().ok()?;
+    let path = PathBuf::from(fields.next()?);
+    Some(Entry {
+        path,
+        rank,
+        last_access,
+    })
+}
+
+// db.zo — двоичный bincode: версия u32, затем список (путь, ранг f64, время u64)
+fn is_zoxide(file: &Path, data: &[u8]) -> bool {
+    file.extension().is_some_and(|ext| ext == "zo") || std::str::from_utf8(data).is_err()
+}
+
+fn parse_zoxide(data: &[u8]) -> io::Result<Vec<Entry>> {
+    let mut reader = Reader { data, position: 0 };
+    let version = reader.u32()?;
+    if version != 3 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Неизвестная версия базы zoxide: {}", version),
+        ));
+    }
+    let count = reader.u64()?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let len = reader.u64()? as usize;
+        let path = String::from_utf8_lossy(reader.bytes(len)?).into_owned();
+        let rank = f64::from_bits(reader.u64()?);
+        let last_access = reader.u64()?;
+        entries.push(Entry {
+            path: PathBuf::from(path),
+            rank,
+            last_access,
+        });
+    }
+    Ok(entries)
+}
+
+// z: «путь|ранг|время», autojump: «вес<TAB>путь»
+fn parse_text(text: &str) -> Vec<Entry> {
+    let now = now();
+    text.lines()
+        .filter_map(|line| {
+            if let Some((weight, path)) = line.split_once('\t') {
+                return Some(Entry {
+                    path: PathBuf::from(path),
+                    rank: weight.trim().parse().ok()?,
+                    last_access: now,
+                });
+            }
+            let mut fields = line.rsplitn(3, '|');
+            let last_access = fields.next()?.trim().parse().ok()?;
+            let rank = fields.next()?.trim().parse().ok()?;
+            let path = PathBuf::from(fields.next()?);
+            Some(Entry {
+                path,
+                rank,
+                last_access,
+            })
+        })
+        .filter(|entry| entry.path.is_absolute() && entry.rank > 0.0)
+        .collect()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "База zoxide обрывается")
+            })?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let mut buffer = [0; 4];
+        buffer.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut buffer = [0; 8];
+        buffer.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_le_bytes(buffer))
+    }
+}
@@ -0,0 +1,235 @@
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+const MAX_CANDIDATES: usize = 200;
+
+// Строка перехода к пути с дополнением по Tab
+#[derive(Default)]
+pub struct GoTo {
+    input: String,
+    // часть ввода до дополняемого имени, вместе с последним /
+    base: String,
+    candidates: Vec<String>,
+    selected: Option<usize>,
+    error: Option<String>,
+}
+
+impl GoTo {
+    // Getters
+    pub fn get_input(&self) -> &String {
+        &self.input
+    }
+
+    pub fn get_candidates(&self) -> &Vec<String> {
+        &self.candidates
+    }
+
+    pub fn get_selected(&self) -> &Option<usize> {
+        &self.selected
+    }
+
+    pub fn get_error(&self) -> &Option<String> {
+        &self.error
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.input.push(c);
+        self.reset();
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+        self.reset();
+    }
+
+    // Первый Tab дописывает общее начало имён и показывает список,
+    // следующие перебирают варианты по кругу
+    pub fn complete(&mut self, current_dir: &Path) {
+        if !self.candidates.is_empty() {
+            self.down();
+            return;
+        }
+        self.error = None;
+
+        let split = self.input.rfind('/').map_or(0, |index| index + 1);
+        let (base, prefix) = self.input.split_at(split);
+        let (base, prefix) = (base.to_string(), prefix.to_string());
+        let dir = if base.is_empty() {
+            Ok(current_dir.to_path_buf())
+        } else {
+            expand(&base, current_dir)
+        };
+        let dir = match dir {
+            Ok(dir) => dir,
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            }
+        };
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.error = Some(format!("{}: {}", dir.display(), err));
+                return;
+            }
+        };
+        // скрытые имена предлагаются, только если ввод начинается с точки
+        let mut candidates: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if !name.starts_with(&prefix) || (name.starts_with('.') && !prefix.starts_with('.'))
+                {
+                    return None;
+                }
+                if entry.path().is_dir() {
+                    Some(name + "/")
+                } else {
+                    Some(name)
+                }
+            })
+            .collect();
+        candidates.sort();
+        candidates.truncate(MAX_CANDIDATES);
+
+        match candidates.len() {
+            0 => self.error = Some(String::from("Нет вариантов")),
+            1 => self.input = format!("{}{}", base, candidates[0]),
+            _ => {
+                self.input = format!("{}{}", base, common_prefix(&candidates));
+                self.base = base;
+                self.candidates = candidates;
+            }
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.candidates.is_empty() {
+            return;
+        }
+        let selected = match self.selected {
+            Some(selected) if selected > 0 => selected - 1,
+            _ => self.candidates.len() - 1,
+        };
+        self.select(selected);
+    }
+
+    pub fn down(&mut self) {
+        if self.candidates.is_empty() {
+            return;
+        }
+        let selected = match self.selected {
+            Some(selected) if selected + 1 < self.candidates.len() => selected + 1,
+            _ => 0,
+        };
+        self.select(selected);
+    }
+
+    // Путь, куда нужно перейти; Enter при открытом списке сначала принимает вариант
+    pub fn accept(&mut self, current_dir: &Path) -> Option<PathBuf> {
+        if self.selected.is_some() {
+            self.reset();
+            return None;
+        }
+        if self.input.trim().is_empty() {
+            self.error = Some(String::from("Введите путь"));
+            return None;
+        }
+        match expand(self.input.trim(), current_dir) {
+            Ok(path) if path.exists() => Some(path),
+            Ok(path) => {
+                self.error = Some(format!("Нет такого пути: {}", path.display()));
+                None
+            }
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+
+    fn select(&mut self, selected: usize) {
+        self.selected = Some(selected);
+        self.input = format!("{}{}", self.base, self.candidates[selected]);
+    }
+
+    fn reset(&mut self) {
+        self.base.clear();
+        self.candidates.clear();
+        self.selected = None;
+        self.error = None;
+    }
+}
+
+// Раскрывает ~, $VAR и ${VAR}; относительный путь считается от текущей папки
+pub fn expand(input: &str, current_dir: &Path) -> Result<PathBuf, String> {
+    let mut expanded = String::new();
+    let mut rest = input;
+    if rest == "~" || rest.starts_with("~/") {
+        let home = env::var("HOME").map_err(|_| String::from("Не задан HOME"))?;
+        expanded.push_str(&home);
+        rest = &rest[1..];
+    }
+
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+        let (name, tail) = if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| String::from("Нет закрывающей } после ${"))?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+        if name.is_empty() {
+            expanded.push('$');
+        } else {
+            let value = env::var(name).map_err(|_| format!("Переменная ${} не задана", name))?;
+            expanded.push_str(&value);
+        }
+        rest = tail;
+    }
+    expanded.push_str(rest);
+
+    Ok(normalize(&current_dir.join(expanded)))
+}
+
+// Убирает . и .. без обращения к диску, чтобы символические ссылки остались в пути
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn common_prefix(candidates: &[String]) -> String {
+    let first = &candidates[0];
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((index, c), _)| index + c.len_utf8())
+            .min(len);
+    }
+    first[..len].to_string()
+}
@@ -1,39 +1,93 @@
 use crate::app::{FileManager, InputMode};
+use crate::error::{AppError, AppResult};
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
-use std::io;
 
-pub fn normal_mode(event: Event, file_manager: &mut FileManager) -> io::Result<()> {
+pub fn normal_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
     if let Event::Key(key) = event {
         match key.code {
-            KeyCode::Char('q') => return Err(io::Error::new(io::ErrorKind::Interrupted, "Quit")),
-            KeyCode::Char('m') => file_manager.menu_mode(),
+            KeyCode::Char('q') => return Err(AppError::Quit),
+            KeyCode::Char('m') => file_manager.set_mark_mode(),
+            KeyCode::F(2) => file_manager.menu_mode(),
+            KeyCode::Char('\'') => file_manager.jump_mark_mode(),
+            KeyCode::Char('B') => file_manager.bookmarks_mode(),
+            KeyCode::Char('z') => file_manager.jump_mode(),
+            KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                file_manager.goto_mode()
+            }
+            KeyCode::Tab => file_manager.switch_panel(),
+            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                file_manager.new_tab()?
+            }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                file_manager.close_tab()?
+            }
+            KeyCode::Char(']') => file_manager.next_tab()?,
+            KeyCode::Char('[') => file_manager.previous_tab()?,
+            KeyCode::Char(c @ '1'..='9') if key.modifiers.contains(KeyModifiers::ALT) => {
+                file_manager.go_to_tab(c as usize - '1' as usize)?
+            }
+            KeyCode::F(5) => file_manager.transfer_prompt(false),
+            KeyCode::F(6) => file_manager.transfer_prompt(true),
             KeyCode::Char('o') => file_manager.context_mode(),
-            KeyCode::Down => file_manager.down(),
-            KeyCode::Up => file_manager.up(),
+            KeyCode::Down => file_manager.down()?,
+            KeyCode::Up => file_manager.up()?,
             KeyCode::PageDown => file_manager.page_down(),
             KeyCode::PageUp => file_manager.page_up(),
+            KeyCode::Left if key.modifiers.contains(KeyModifiers::ALT) => file_manager.go_back()?,
+            KeyCode::Right if key.modifiers.contains(KeyModifiers::ALT) => {
+                file_manager.go_forward()?
+            }
+            KeyCode::Char('<') => file_manager.go_back()?,
+            KeyCode::Char('>') => file_manager.go_forward()?,
             KeyCode::Enter | KeyCode::Right => file_manager.enter_handler()?,
             KeyCode::Backspace | KeyCode::Left => file_manager.to_parent_dir()?,
             KeyCode::Char('f') => file_manager.search_mode(),
+            KeyCode::Esc if file_manager.is_visual() => file_manager.cancel_visual(),
+            KeyCode::Esc => file_manager.clear_filter(),
+            KeyCode::Char(' ') => file_manager.toggle_mark()?,
+            KeyCode::Char('v') => file_manager.toggle_visual(),
+            KeyCode::Char('+') => file_manager.select_glob_prompt(),
+            KeyCode::Char('*') => file_manager.invert_marks(),
+            KeyCode::Char('-') => file_manager.clear_marks(),
+            KeyCode::Char('y') => file_manager.yank_selected(false),
+            KeyCode::Char('x') => file_manager.yank_selected(true),
+            KeyCode::Char('p') => file_manager.paste()?,
+            KeyCode::Char('J') => file_manager.jobs_mode(),
+            KeyCode::Char('d') | KeyCode::Delete => file_manager.trash_selected(),
+            KeyCode::Char('D') => file_manager.delete_permanently_prompt(),
+            KeyCode::Char('T') => file_manager.trash_mode(),
+            KeyCode::Char('u') => file_manager.undo()?,
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                file_manager.redo()?
+            }
+            KeyCode::Char('h') => file_manager.history_mode(),
+            KeyCode::Char('s') => file_manager.sort_mode(),
+            KeyCode::Char('P') => file_manager.properties_mode()?,
+            KeyCode::Char('c') => file_manager.permissions_mode()?,
+            KeyCode::Char('t') => file_manager.jump_to_link_target()?,
+            KeyCode::Char('R') => file_manager.bulk_rename()?,
+            KeyCode::Char('r') => file_manager.batch_rename_mode(),
+            KeyCode::Char('l') => file_manager.toggle_detailed(),
+            KeyCode::Char('L') => file_manager.columns_mode(),
+            KeyCode::Char('.') => file_manager.toggle_hidden()?,
+            KeyCode::Char('i') => file_manager.toggle_ignore_files()?,
+            KeyCode::Char('I') => file_manager.ignore_patterns_prompt(),
+            KeyCode::Char('F') => file_manager.recursive_search_mode(),
+            KeyCode::Char('g') => file_manager.content_search_mode(),
             _ => {}
         }
     }
     Ok(())
 }
 
-pub fn menu_mode(event: Event, file_manager: &mut FileManager) -> io::Result<()> {
+pub fn menu_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
     match file_manager.get_input_mode() {
-        InputMode::Input => {
-            input_mode(event, file_manager).unwrap_or_else(|err| eprintln!("Error: {}", err))
-        }
-        InputMode::Normal => handle_normal_input_mode(event, file_manager)
-            .unwrap_or_else(|err| eprintln!("Error: {}", err)),
+        InputMode::Input => input_mode(event, file_manager),
+        InputMode::Normal => handle_normal_input_mode(event, file_manager),
     }
-
-    Ok(())
 }
 
-pub fn context_mode(event: Event, file_manager: &mut FileManager) -> io::Result<()> {
+pub fn context_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
     if let Event::Key(key) = event {
         match key.code {
             KeyCode::Esc | KeyCode::Backspace => file_manager.default_input_mode(),
@@ -46,19 +100,252 @@ pub fn context_mode(event: Event, file_manager: &mut FileManager) -> io::Result<
     Ok(())
 }
 
-pub fn search_mode(event: Event, file_manager: &mut FileManager) -> io::Result<()> {
+pub fn conflict_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc => file_manager.cancel_paste(),
+            KeyCode::Down => file_manager.conflict_down(),
+            KeyCode::Up => file_manager.conflict_up(),
+            KeyCode::Enter | KeyCode::Right => file_manager.select_from_conflict()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn jobs_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('J') => file_manager.default_mode(),
+            KeyCode::Down => file_manager.jobs_down(),
+            KeyCode::Up => file_manager.jobs_up(),
+            KeyCode::Char('c') | KeyCode::Delete => file_manager.cancel_job(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn trash_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc | KeyCode::Backspace => file_manager.default_mode(),
+            KeyCode::Down => file_manager.trash_down(),
+            KeyCode::Up => file_manager.trash_up(),
+            KeyCode::Enter | KeyCode::Char('r') => file_manager.restore_from_trash()?,
+            KeyCode::Char('D') | KeyCode::Delete => file_manager.purge_from_trash(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn sort_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('s') => file_manager.default_mode(),
+            KeyCode::Down => file_manager.sort_down(),
+            KeyCode::Up => file_manager.sort_up(),
+            KeyCode::Enter | KeyCode::Char(' ') => file_manager.select_from_sort()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn columns_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('L') => file_manager.default_mode(),
+            KeyCode::Down => file_manager.columns_down(),
+            KeyCode::Up => file_manager.columns_up(),
+            KeyCode::Enter | KeyCode::Char(' ') => file_manager.toggle_column(),
+            KeyCode::Left | KeyCode::Char('-') => file_manager.resize_column(-1),
+            KeyCode::Right | KeyCode::Char('+') => file_manager.resize_column(1),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn properties_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('P') => file_manager.default_mode(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn permissions_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc => file_manager.close_permissions(),
+            KeyCode::Tab | KeyCode::Down | KeyCode::Right => file_manager.permissions_next(),
+            KeyCode::BackTab | KeyCode::Up | KeyCode::Left => file_manager.permissions_previous(),
+            KeyCode::Char(' ') => file_manager.permissions_toggle(),
+            KeyCode::Char(c) => file_manager.permissions_push(c),
+            KeyCode::Backspace => file_manager.permissions_pop(),
+            KeyCode::Enter => file_manager.apply_permissions()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn batch_rename_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc => file_manager.close_batch_rename(),
+            KeyCode::Tab | KeyCode::Down => file_manager.batch_rename_next(),
+            KeyCode::BackTab | KeyCode::Up => file_manager.batch_rename_previous(),
+            KeyCode::Char(' ') => file_manager.batch_rename_toggle(),
+            KeyCode::Char(c) => file_manager.batch_rename_push(c),
+            KeyCode::Backspace => file_manager.batch_rename_pop(),
+            KeyCode::Enter => file_manager.apply_batch_rename()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn jump_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(KeyEvent {
+        code, modifiers, ..
+    }) = event
+    {
+        if modifiers == KeyModifiers::CONTROL {
+            if code == KeyCode::Char('c') {
+                file_manager.close_jump();
+            }
+            return Ok(());
+        }
+        match code {
+            KeyCode::Esc => file_manager.close_jump(),
+            KeyCode::Char(c) => file_manager.add_to_jump(c),
+            KeyCode::Backspace => file_manager.delete_from_jump(),
+            KeyCode::Down => file_manager.jump_down(),
+            KeyCode::Up => file_manager.jump_up(),
+            KeyCode::Enter => file_manager.select_from_jump()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn goto_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(KeyEvent {
+        code, modifiers, ..
+    }) = event
+    {
+        if modifiers == KeyModifiers::CONTROL {
+            if code == KeyCode::Char('c') {
+                file_manager.close_goto();
+            }
+            return Ok(());
+        }
+        match code {
+            KeyCode::Esc => file_manager.close_goto(),
+            KeyCode::Char(c) => file_manager.add_to_goto(c),
+            KeyCode::Backspace => file_manager.delete_from_goto(),
+            KeyCode::Tab => file_manager.complete_goto(),
+            KeyCode::Down => file_manager.goto_down(),
+            KeyCode::Up | KeyCode::BackTab => file_manager.goto_up(),
+            KeyCode::Enter => file_manager.select_from_goto(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn set_mark_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Char(c) if c.is_ascii_alphabetic() => file_manager.set_bookmark(c)?,
+            KeyCode::Enter => file_manager.menu_mode(),
+            _ => file_manager.cancel_pending(),
+        }
+    }
+    Ok(())
+}
+
+pub fn jump_mark_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Char(c) if c.is_ascii_alphabetic() => file_manager.jump_to_bookmark(c)?,
+            KeyCode::Char('\'') => file_manager.bookmarks_mode(),
+            _ => file_manager.cancel_pending(),
+        }
+    }
+    Ok(())
+}
+
+pub fn bookmarks_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('B') => file_manager.default_mode(),
+            KeyCode::Down => file_manager.bookmarks_down(),
+            KeyCode::Up => file_manager.bookmarks_up(),
+            KeyCode::Enter | KeyCode::Right => file_manager.select_from_bookmarks()?,
+            KeyCode::Char('r') => file_manager.rename_bookmark_prompt(),
+            KeyCode::Char('d') | KeyCode::Delete => file_manager.delete_bookmark()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn history_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('h') => file_manager.default_mode(),
+            KeyCode::Char('u') => file_manager.undo()?,
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                file_manager.redo()?
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn confirm_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Char('д') => {
+                file_manager.confirm_yes()?
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('н') | KeyCode::Esc => {
+                file_manager.confirm_no()
+            }
+            KeyCode::Left | KeyCode::Right | KeyCode::Tab => file_manager.confirm_toggle(),
+            KeyCode::Enter => file_manager.confirm_selected()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn search_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
     if let Event::Key(KeyEvent {
         code, modifiers, ..
     }) = event
     {
         if modifiers == KeyModifiers::CONTROL && code == KeyCode::Char('c') {
-            file_manager.default_mode()
+            file_manager.clear_filter();
+            return Ok(());
         }
         match code {
+            // фильтр остаётся, с отфильтрованным списком работают уже в обычном режиме
+            KeyCode::Esc => file_manager.confirm_filter(),
             KeyCode::Char(c) => file_manager.add_to_search_buffer(c),
             KeyCode::Backspace => file_manager.delete_from_search_buffer(),
+            KeyCode::Down => file_manager.down()?,
+            KeyCode::Up => file_manager.up()?,
             KeyCode::Enter => {
-                file_manager.find_in_current_dir();
+                file_manager.confirm_filter();
+                file_manager.enter_handler()?;
             }
             _ => {}
         }
@@ -66,13 +353,68 @@ pub fn search_mode(event: Event, file_manager: &mut FileManager) -> io::Result<(
     Ok(())
 }
 
-fn input_mode(event: Event, file_manager: &mut FileManager) -> io::Result<()> {
+pub fn recursive_search_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
     if let Event::Key(KeyEvent {
         code, modifiers, ..
     }) = event
     {
-        if modifiers == KeyModifiers::CONTROL && code == KeyCode::Char('c') {
-            file_manager.default_input_mode()
+        if modifiers == KeyModifiers::CONTROL {
+            match code {
+                KeyCode::Char('c') => file_manager.close_recursive_search(),
+                KeyCode::Char('u') => file_manager.recursive_search_root_to_parent(),
+                _ => {}
+            }
+            return Ok(());
+        }
+        match code {
+            KeyCode::Esc => file_manager.close_recursive_search(),
+            KeyCode::Char(c) => file_manager.add_to_recursive_search(c),
+            KeyCode::Backspace => file_manager.delete_from_recursive_search(),
+            KeyCode::Down => file_manager.recursive_search_down(),
+            KeyCode::Up => file_manager.recursive_search_up(),
+            KeyCode::Enter => file_manager.select_from_recursive_search()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn content_search_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(KeyEvent {
+        code, modifiers, ..
+    }) = event
+    {
+        if modifiers == KeyModifiers::CONTROL {
+            match code {
+                KeyCode::Char('c') => file_manager.close_content_search(),
+                KeyCode::Char('r') => file_manager.toggle_content_search_regex(),
+                _ => {}
+            }
+            return Ok(());
+        }
+        match code {
+            KeyCode::Esc => file_manager.close_content_search(),
+            KeyCode::Char(c) => file_manager.add_to_content_search(c),
+            KeyCode::Backspace => file_manager.delete_from_content_search(),
+            KeyCode::Down => file_manager.content_search_down(),
+            KeyCode::Up => file_manager.content_search_up(),
+            KeyCode::Enter => file_manager.content_search_enter()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn input_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
+    if let Event::Key(KeyEvent {
+        code, modifiers, ..
+    }) = event
+    {
+        if code == KeyCode::Esc
+            || (modifiers == KeyModifiers::CONTROL && code == KeyCode::Char('c'))
+        {
+            file_manager.cancel_input();
+            return Ok(());
         }
         match code {
             KeyCode::Char(c) => file_manager.add_to_input_buffer(c),
@@ -86,7 +428,7 @@ fn input_mode(event: Event, file_manager: &mut FileManager) -> io::Result<()> {
     Ok(())
 }
 
-fn handle_normal_input_mode(event: Event, file_manager: &mut FileManager) -> io::Result<()> {
+fn handle_normal_input_mode(event: Event, file_manager: &mut FileManager) -> AppResult {
     if let Event::Key(key) = event {
         match key.code {
             KeyCode::Esc | KeyCode::Backspace => file_manager.default_mode(),
@@ -0,0 +1,420 @@
+use crate::file_ops::{self, Attributes};
+use crate::journal::{Change, Step};
+use crate::trash::{self, TrashEntry};
+use std::collections::HashSet;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Сколько показывать завершённую задачу в панели
+const FINISHED_TTL: Duration = Duration::from_secs(3);
+const FAILED_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct Progress {
+    bytes_total: AtomicU64,
+    bytes_done: AtomicU64,
+    files_total: AtomicU64,
+    files_done: AtomicU64,
+    scanned: AtomicBool,
+    cancel: AtomicBool,
+}
+
+impl Progress {
+    pub fn add(&self, bytes: u64, files: u64) {
+        self.bytes_done.fetch_add(bytes, Ordering::Relaxed);
+        self.files_done.fetch_add(files, Ordering::Relaxed);
+    }
+
+    pub fn check(&self) -> io::Result<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "Отменено"));
+        }
+        Ok(())
+    }
+
+    fn set_totals(&self, bytes: u64, files: u64) {
+        self.bytes_total.store(bytes, Ordering::Relaxed);
+        self.files_total.store(files, Ordering::Relaxed);
+        self.scanned.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone)]
+pub struct Transfer {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub overwrite: bool,
+    // заменённый при перезаписи элемент, его вернёт из корзины отмена
+    pub replaced: Option<TrashEntry>,
+}
+
+pub enum Operation {
+    Copy(Vec<Transfer>),
+    Move(Vec<Transfer>),
+    Delete(Vec<PathBuf>),
+    SetAttributes(Vec<PathBuf>, Attributes),
+}
+
+impl Operation {
+    fn sources(&self) -> Vec<PathBuf> {
+        match self {
+            Operation::Copy(transfers) | Operation::Move(transfers) => transfers
+                .iter()
+                .map(|transfer| transfer.source.clone())
+                .collect(),
+            Operation::Delete(paths) | Operation::SetAttributes(paths, _) => paths.clone(),
+        }
+    }
+
+    // В done попадают перенесённые элементы, даже если задача потом упала или её отменили
+    fn run(self, progress: &Progress, done: &mut Vec<Transfer>) -> io::Result<()> {
+        let sizes: Vec<(u64, u64)> = self
+            .sources()
+            .iter()
+            .map(|path| file_ops::measure(path))
+            .collect();
+        let (bytes, files) = sizes
+            .iter()
+            .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f));
+        progress.set_totals(bytes, files);
+
+        match self {
+            // ошибка одного элемента (например, занятое имя) не останавливает остальные
+            Operation::Copy(transfers) => {
+                let mut failed = Vec::new();
+                for mut transfer in transfers {
+                    let result = Self::prepare_target(&mut transfer).and_then(|_| {
+                        file_ops::copy_path(&transfer.source, &transfer.target, progress)
+                    });
+                    Self::collect(transfer, result, &mut failed, done)?;
+                }
+                Self::report(&failed, "скопировать")?;
+            }
+            Operation::Move(transfers) => {
+                let mut failed = Vec::new();
+                for (mut transfer, size) in transfers.into_iter().zip(sizes) {
+                    let result = Self::prepare_target(&mut transfer).and_then(|_| {
+                        file_ops::move_path(&transfer.source, &transfer.target, size, progress)
+                    });
+                    Self::collect(transfer, result, &mut failed, done)?;
+                }
+                Self::report(&failed, "переместить")?;
+            }
+            Operation::Delete(paths) => {
+                for path in paths {
+                    file_ops::remove_path(&path, progress)?;
+                }
+            }
+            Operation::SetAttributes(paths, attributes) => {
+                let mut failed = Vec::new();
+                for path in paths {
+                    file_ops::set_attributes(&path, &attributes, progress, &mut failed)?;
+                }
+                Self::report(&failed, "изменить")?;
+            }
+        }
+        Ok(())
+    }
+
+    // Отмена прерывает задачу целиком, прочие ошибки запоминаются для отчёта
+    fn collect(
+        transfer: Transfer,
+        result: io::Result<()>,
+        failed: &mut Vec<(PathBuf, io::Error)>,
+        done: &mut Vec<Transfer>,
+    ) -> io::Result<()> {
+        match result {
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => Err(err),
+            Err(err) => {
+                failed.push((transfer.source, err));
+                Ok(())
+            }
+            Ok(()) => {
+                done.push(transfer);
+                Ok(())
+            }
+        }
+    }
+
+    fn report(failed: &[(PathBuf, io::Error)], action: &str) -> io::Result<()> {
+        if let Some((path, err)) = failed.first() {
+            return Err(io::Error::new(
+                err.kind(),
+                format!(
+                    "Не удалось {} {}: {} — {}",
+                    action,
+                    failed.len(),
+                    path.display(),
+                    err
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    // Заменяемый элемент уходит в корзину, чтобы перезапись можно было исправить
+    fn prepare_target(transfer: &mut Transfer) -> io::Result<()> {
+        if transfer.overwrite && file_ops::exists(&transfer.target) {
+            transfer.replaced = Some(trash::trash(&transfer.target)?);
+        }
+        Ok(())
+    }
+}
+
+// Превращает перенесённые элементы в запись журнала
+pub type Recorder = fn(Vec<Transfer>) -> Change;
+
+// Как записать задачу переноса в журнал, когда она закончится
+struct Record {
+    step: Step,
+    change: Recorder,
+    transfers: Vec<Transfer>,
+}
+
+// Результат задачи для журнала: перенесённое и то, что перенести не удалось
+pub struct Recorded {
+    pub step: Step,
+    pub done: Option<Change>,
+    pub rest: Option<Change>,
+}
+
+pub enum JobState {
+    Running,
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+pub struct Job {
+    title: String,
+    progress: Arc<Progress>,
+    started: Instant,
+    finished: Option<Instant>,
+    state: JobState,
+    receiver: Receiver<(Vec<Transfer>, io::Result<()>)>,
+    record: Option<Record>,
+    recorded: Option<Recorded>,
+}
+
+impl Job {
+    fn spawn(title: String, operation: Operation, record: Option<(Step, Recorder)>) -> Self {
+        let progress = Arc::new(Progress::default());
+        let (sender, receiver) = mpsc::channel();
+        let record = record.and_then(|(step, change)| match &operation {
+            Operation::Copy(transfers) | Operation::Move(transfers) => Some(Record {
+                step,
+                change,
+                transfers: transfers.clone(),
+            }),
+            _ => None,
+        });
+
+        let worker_progress = progress.clone();
+        thread::spawn(move || {
+            let mut done = Vec::new();
+            let result = operation.run(&worker_progress, &mut done);
+            let _ = sender.send((done, result));
+        });
+
+        Self {
+            title,
+            progress,
+            started: Instant::now(),
+            finished: None,
+            state: JobState::Running,
+            receiver,
+            record,
+            recorded: None,
+        }
+    }
+
+    // Getters
+    pub fn get_title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn get_state(&self) -> &JobState {
+        &self.state
+    }
+
+    pub fn ratio(&self) -> f64 {
+        let (done, total) = self.amount();
+        if total == 0 {
+            return if self.finished.is_some() { 1.0 } else { 0.0 };
+        }
+        (done as f64 / total as f64).min(1.0)
+    }
+
+    pub fn eta(&self) -> Option<Duration> {
+        let (done, total) = self.amount();
+        if done == 0 || total == 0 || self.finished.is_some() {
+            return None;
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        Some(Duration::from_secs_f64(
+            elapsed * (total - done.min(total)) as f64 / done as f64,
+        ))
+    }
+
+    pub fn is_scanned(&self) -> bool {
+        self.progress.scanned.load(Ordering::Relaxed)
+    }
+
+    pub fn get_files(&self) -> (u64, u64) {
+        (
+            self.progress.files_done.load(Ordering::Relaxed),
+            self.progress.files_total.load(Ordering::Relaxed),
+        )
+    }
+
+    pub fn get_bytes(&self) -> (u64, u64) {
+        (
+            self.progress.bytes_done.load(Ordering::Relaxed),
+            self.progress.bytes_total.load(Ordering::Relaxed),
+        )
+    }
+
+    // Прогресс по байтам, а если их нет (удаление пустых файлов) — по файлам
+    fn amount(&self) -> (u64, u64) {
+        let (bytes_done, bytes_total) = self.get_bytes();
+        if bytes_total > 0 {
+            return (bytes_done, bytes_total);
+        }
+        self.get_files()
+    }
+
+    fn cancel(&self) {
+        self.progress.cancel.store(true, Ordering::Relaxed);
+    }
+
+    fn poll(&mut self) -> bool {
+        if self.finished.is_some() {
+            return false;
+        }
+        let (done, result) = match self.receiver.try_recv() {
+            Ok(received) => received,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => {
+                (Vec::new(), Err(io::Error::other("Задача прервана")))
+            }
+        };
+
+        if let Some(record) = self.record.take() {
+            let rest: Vec<Transfer> = {
+                let moved: HashSet<&PathBuf> =
+                    done.iter().map(|transfer| &transfer.source).collect();
+                record
+                    .transfers
+                    .into_iter()
+                    .filter(|transfer| !moved.contains(&transfer.source))
+                    .collect()
+            };
+            self.recorded = Some(Recorded {
+                step: record.step,
+                done: (!done.is_empty()).then(|| (record.change)(done)),
+                rest: (!rest.is_empty()).then(|| (record.change)(rest)),
+            });
+        }
+
+        self.state = match result {
+            Ok(()) => JobState::Done,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => JobState::Cancelled,
+            Err(err) => JobState::Failed(err.to_string()),
+        };
+        self.finished = Some(Instant::now());
+        true
+    }
+
+    fn is_expired(&self) -> bool {
+        let ttl = match self.state {
+            JobState::Failed(_) => FAILED_TTL,
+            _ => FINISHED_TTL,
+        };
+        self.finished
+            .is_some_and(|finished| finished.elapsed() > ttl)
+    }
+}
+
+#[derive(Default)]
+pub struct JobQueue {
+    jobs: Vec<Job>,
+    selected: usize,
+}
+
+impl JobQueue {
+    pub fn get_jobs(&self) -> &Vec<Job> {
+        &self.jobs
+    }
+
+    pub fn get_selected(&self) -> &usize {
+        &self.selected
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    pub fn submit(&mut self, title: String, operation: Operation) {
+        self.jobs.push(Job::spawn(title, operation, None));
+    }
+
+    // Копирование и перемещение, которые попадут в журнал, когда задача закончится
+    pub fn submit_recorded(
+        &mut self,
+        title: String,
+        operation: Operation,
+        step: Step,
+        change: Recorder,
+    ) {
+        self.jobs
+            .push(Job::spawn(title, operation, Some((step, change))));
+    }
+
+    // Результаты завершившихся задач переноса для журнала
+    pub fn take_recorded(&mut self) -> Vec<Recorded> {
+        self.jobs
+            .iter_mut()
+            .filter_map(|job| job.recorded.take())
+            .collect()
+    }
+
+    // Возвращает true, если какая-то задача завершилась
+    pub fn poll(&mut self) -> bool {
+        let mut finished = false;
+        for job in &mut self.jobs {
+            finished |= job.poll();
+        }
+        self.jobs.retain(|job| !job.is_expired());
+        if self.selected >= self.jobs.len() {
+            self.selected = self.jobs.len().saturating_sub(1);
+        }
+        finished
+    }
+
+    pub fn cancel_selected(&mut self) {
+        if let Some(job) = self.jobs.get(self.selected) {
+            job.cancel();
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        } else {
+            self.selected = self.jobs.len().saturating_sub(1);
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.jobs.len() {
+            self.selected += 1;
+        } else {
+            self.selected = 0;
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use crate::file_ops::{self, LinkKind};
+use crate::jobs::{JobQueue, Operation, Recorded, Transfer};
+use crate::trash::{self, TrashEntry};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Сколько операций хранится для отмены
+const JOURNAL_LIMIT: usize = 100;
+
+// Ошибка посреди операции из нескольких элементов: уже сделанная часть
+// переходит в другой стек, а в операции остаются только несделанные элементы
+type Outcome = Result<(), (Option<Change>, io::Error)>;
+
+// Откуда запущена задача переноса: от этого зависит, в какой стек попадёт её результат
+#[derive(Clone, Copy)]
+pub enum Step {
+    Do,
+    Undo,
+    Redo,
+}
+
+// Операция с файлами, которую можно отменить и повторить
+pub enum Change {
+    Create {
+        path: PathBuf,
+        dir: bool,
+    },
+    Link {
+        link: PathBuf,
+        target: PathBuf,
+        kind: LinkKind,
+    },
+    Rename(Vec<(PathBuf, PathBuf)>),
+    Trash(Vec<TrashEntry>),
+    Move(Vec<Transfer>),
+    Copy(Vec<Transfer>),
+}
+
+impl Change {
+    pub fn describe(&self) -> String {
+        match self {
+            Change::Create { path, dir: true } => format!("Создание папки {}", name(path)),
+            Change::Create { path, .. } => format!("Создание файла {}", name(path)),
+            Change::Link { link, .. } => format!("Создание ссылки {}", name(link)),
+            Change::Rename(renames) => match renames.as_slice() {
+                [(from, to)] => format!("Переименование {} → {}", name(from), name(to)),
+                _ => format!("Переименование {} элементов", renames.len()),
+            },
+            Change::Trash(entries) => match entries.as_slice() {
+                [entry] => format!("Удаление в корзину {}", name(&entry.original)),
+                _ => format!("Удаление в корзину {} элементов", entries.len()),
+            },
+            Change::Move(transfers) => format!("Перемещение {} элементов", transfers.len()),
+            Change::Copy(transfers) => format!("Копирование {} элементов", transfers.len()),
+        }
+    }
+
+    fn undo(&mut self, jobs: &mut JobQueue) -> Outcome {
+        match self {
+            // в созданный файл могли успеть что-то записать, поэтому он уходит в корзину
+            Change::Create { path, .. } => single(trash::trash(path).map(|_| ())),
+            Change::Link { link, .. } => single(fs::remove_file(link)),
+            Change::Rename(renames) => each_rev(renames, Change::Rename, |(from, to)| {
+                file_ops::rename_noreplace(to, from)
+            }),
+            Change::Trash(entries) => each(entries, Change::Trash, |entry| trash::restore(entry)),
+            // в журнал записывается то, что задача действительно вернёт на место
+            Change::Move(transfers) => {
+                jobs.submit_recorded(
+                    String::from("Отмена перемещения"),
+                    Operation::Move(reversed(transfers)),
+                    Step::Undo,
+                    |transfers| Change::Move(reversed(&transfers)),
+                );
+                Ok(())
+            }
+            // копии уходят в корзину, чтобы ошибочную отмену тоже можно было исправить,
+            // а заменённые ими элементы возвращаются на место
+            Change::Copy(transfers) => each(transfers, Change::Copy, |transfer| {
+                trash::trash(&transfer.target)?;
+                restore_replaced(transfer)
+            }),
+        }
+    }
+
+    fn redo(&mut self, jobs: &mut JobQueue) -> Outcome {
+        match self {
+            Change::Create { path, dir } => {
+                if file_ops::exists(path) {
+                    return single(Err(already_exists(path)));
+                }
+                single(if *dir {
+                    fs::create_dir(path)
+                } else {
+                    File::create(path).map(|_| ())
+                })
+            }
+            Change::Link { link, target, kind } => {
+                single(file_ops::create_link(target, link, *kind))
+            }
+            Change::Rename(renames) => each(renames, Change::Rename, |(from, to)| {
+                file_ops::rename_noreplace(from, to)
+            }),
+            // повторное удаление создаёт в корзине новые записи
+            Change::Trash(entries) => each(entries, Change::Trash, |entry| {
+                *entry = trash::trash(&entry.original)?;
+                Ok(())
+            }),
+            Change::Move(transfers) => {
+                jobs.submit_recorded(
+                    String::from("Повтор перемещения"),
+                    Operation::Move(for_redo(transfers)),
+                    Step::Redo,
+                    Change::Move,
+                );
+                Ok(())
+            }
+            Change::Copy(transfers) => {
+                jobs.submit_recorded(
+                    String::from("Повтор копирования"),
+                    Operation::Copy(for_redo(transfers)),
+                    Step::Redo,
+                    Change::Copy,
+                );
+                Ok(())
+            }
+        }
+    }
+
+    // Такие шаги выполняет фоновая задача, и в журнал их записывает finish
+    fn in_background(&self, step: Step) -> bool {
+        matches!(
+            (self, step),
+            (Change::Move(_), _) | (Change::Copy(_), Step::Redo)
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Journal {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+impl Journal {
+    pub fn get_undo(&self) -> &Vec<Change> {
+        &self.undo
+    }
+
+    pub fn get_redo(&self) -> &Vec<Change> {
+        &self.redo
+    }
+
+    pub fn record(&mut self, change: Change) {
+        self.push_undo(change);
+        self.redo.clear();
+    }
+
+    // Перенесённое задачей уходит в свой стек, а то, что перенести не удалось,
+    // возвращается туда, откуда было взято
+    pub fn finish(&mut self, recorded: Recorded) -> io::Result<()> {
+        let Recorded { step, done, rest } = recorded;
+        // после отмены перемещения на освободившееся место возвращается заменённое
+        let restored = match &done {
+            Some(Change::Move(transfers)) if matches!(step, Step::Undo) => {
+                transfers.iter().try_for_each(restore_replaced)
+            }
+            _ => Ok(()),
+        };
+        match step {
+            Step::Do => {
+                if let Some(done) = done {
+                    self.record(done);
+                }
+            }
+            Step::Undo => {
+                self.redo.extend(done);
+                if let Some(rest) = rest {
+                    self.push_undo(rest);
+                }
+            }
+            Step::Redo => {
+                if let Some(done) = done {
+                    self.push_undo(done);
+                }
+                self.redo.extend(rest);
+            }
+        }
+        restored
+    }
+
+    fn push_undo(&mut self, change: Change) {
+        self.undo.push(change);
+        if self.undo.len() > JOURNAL_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    // При ошибке несделанная часть операции остаётся в стеке, чтобы можно было
+    // повторить попытку, а сделанная переходит в стек повтора
+    pub fn undo(&mut self, jobs: &mut JobQueue) -> io::Result<Option<String>> {
+        let Some(change) = self.undo.last_mut() else {
+            return Ok(None);
+        };
+        if let Err((done, err)) = change.undo(jobs) {
+            self.redo.extend(done);
+            return Err(err);
+        }
+
+        let change = self.undo.pop().unwrap();
+        let description = change.describe();
+        if !change.in_background(Step::Undo) {
+            self.redo.push(change);
+        }
+        Ok(Some(description))
+    }
+
+    pub fn redo(&mut self, jobs: &mut JobQueue) -> io::Result<Option<String>> {
+        let Some(change) = self.redo.last_mut() else {
+            return Ok(None);
+        };
+        if let Err((done, err)) = change.redo(jobs) {
+            self.undo.extend(done);
+            return Err(err);
+        }
+
+        let change = self.redo.pop().unwrap();
+        let description = change.describe();
+        if !change.in_background(Step::Redo) {
+            self.push_undo(change);
+        }
+        Ok(Some(description))
+    }
+}
+
+fn single(result: io::Result<()>) -> Outcome {
+    result.map_err(|err| (None, err))
+}
+
+// Применяет шаг к элементам по порядку. При ошибке в items остаются
+// необработанные элементы, а обработанные возвращаются отдельной операцией
+fn each<T>(
+    items: &mut Vec<T>,
+    change: fn(Vec<T>) -> Change,
+    mut step: impl FnMut(&mut T) -> io::Result<()>,
+) -> Outcome {
+    for index in 0..items.len() {
+        if let Err(err) = step(&mut items[index]) {
+            let rest = items.split_off(index);
+            let done = std::mem::replace(items, rest);
+            return Err(((!done.is_empty()).then(|| change(done)), err));
+        }
+    }
+    Ok(())
+}
+
+// То же с конца списка: отмена идёт в обратном порядке
+fn each_rev<T>(
+    items: &mut Vec<T>,
+    change: fn(Vec<T>) -> Change,
+    mut step: impl FnMut(&mut T) -> io::Result<()>,
+) -> Outcome {
+    for index in (0..items.len()).rev() {
+        if let Err(err) = step(&mut items[index]) {
+            let done = items.split_off(index + 1);
+            return Err(((!done.is_empty()).then(|| change(done)), err));
+        }
+    }
+    Ok(())
+}
+
+fn reversed(transfers: &[Transfer]) -> Vec<Transfer> {
+    transfers
+        .iter()
+        .map(|transfer| Transfer {
+            source: transfer.target.clone(),
+            target: transfer.source.clone(),
+            overwrite: false,
+            replaced: transfer.replaced.clone(),
+        })
+        .collect()
+}
+
+// Повтор снова перезаписывает только те цели, которые заменял в первый раз
+// и которые отмена вернула из корзины
+fn for_redo(transfers: &[Transfer]) -> Vec<Transfer> {
+    transfers
+        .iter()
+        .map(|transfer| Transfer {
+            source: transfer.source.clone(),
+            target: transfer.target.clone(),
+            overwrite: transfer.replaced.is_some(),
+            replaced: None,
+        })
+        .collect()
+}
+
+fn restore_replaced(transfer: &Transfer) -> io::Result<()> {
+    match &transfer.replaced {
+        Some(entry) => trash::restore(entry),
+        None => Ok(()),
+    }
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} уже существует", path.display()),
+    )
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
@@ -1,50 +1,130 @@
 use std::io;
-use tui::{
-    backend::{Backend, CrosstermBackend},
-    widgets::Widget,
-    Terminal,
-};
+use std::panic;
+use std::process;
+use std::time::Duration;
+use tui::{backend::CrosstermBackend, Terminal};
 
 use crossterm::{
+    cursor::Show,
     event, execute,
-    terminal::{disable_raw_mode, enable_raw_mode, LeaveAlternateScreen},
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
 mod app;
+mod batch_rename;
+mod bookmarks;
+mod config;
+mod error;
+mod file_ops;
+mod frecency;
+mod goto;
 mod input_handler;
+mod jobs;
+mod journal;
+mod owners;
+mod panel;
+mod permissions;
+mod properties;
+mod rename;
+mod search;
+mod sort;
+mod trash;
 mod ui;
+mod view;
+mod visibility;
 use crate::app::{FileManager, Mode};
+use crate::error::{AppError, AppResult};
 use ui::render;
 
-fn main() -> io::Result<()> {
+const TICK_RATE: Duration = Duration::from_millis(100);
+
+fn main() {
+    // терминал возвращается в обычный режим даже при панике
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+
+    let result = run();
+    restore_terminal();
+
+    if let Err(AppError::Fatal(err) | AppError::Recoverable(err)) = result {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run() -> AppResult {
     // init terminal
-    enable_raw_mode()?;
+    enable_raw_mode().map_err(AppError::Fatal)?;
+    execute!(io::stdout(), EnterAlternateScreen).map_err(AppError::Fatal)?;
     let stdout = io::stdout();
     let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    terminal.clear()?;
+    let mut terminal = Terminal::new(backend).map_err(AppError::Fatal)?;
+    terminal.clear().map_err(AppError::Fatal)?;
 
     // init App
-    let mut file_manager = FileManager::new().expect("Error FileManager init");
+    let mut file_manager = FileManager::new().map_err(AppError::Fatal)?;
 
+    // экран перерисовывается после событий и пока идут фоновые задачи и поиск
+    let mut redraw = true;
     loop {
-        terminal.draw(|f| render(f, &file_manager))?;
+        redraw |= file_manager.tick();
+        if redraw {
+            terminal
+                .draw(|f| render(f, &file_manager))
+                .map_err(AppError::Fatal)?;
+            redraw = false;
+        }
+
+        // ждём событие ограниченное время, чтобы фоновые задачи обновляли экран
+        if !event::poll(TICK_RATE).map_err(AppError::Fatal)? {
+            continue;
+        }
 
-        let event = event::read()?;
+        let event = event::read().map_err(AppError::Fatal)?;
+        redraw = true;
         let result = match file_manager.get_mode() {
             Mode::Normal => input_handler::normal_mode(event, &mut file_manager),
             Mode::Menu => input_handler::menu_mode(event, &mut file_manager),
             Mode::Search => input_handler::search_mode(event, &mut file_manager),
+            Mode::RecursiveSearch => input_handler::recursive_search_mode(event, &mut file_manager),
+            Mode::ContentSearch => input_handler::content_search_mode(event, &mut file_manager),
             Mode::Context => input_handler::context_mode(event, &mut file_manager),
+            Mode::Conflict => input_handler::conflict_mode(event, &mut file_manager),
+            Mode::Jobs => input_handler::jobs_mode(event, &mut file_manager),
+            Mode::Trash => input_handler::trash_mode(event, &mut file_manager),
+            Mode::Confirm => input_handler::confirm_mode(event, &mut file_manager),
+            Mode::History => input_handler::history_mode(event, &mut file_manager),
+            Mode::Sort => input_handler::sort_mode(event, &mut file_manager),
+            Mode::Columns => input_handler::columns_mode(event, &mut file_manager),
+            Mode::Properties => input_handler::properties_mode(event, &mut file_manager),
+            Mode::Permissions => input_handler::permissions_mode(event, &mut file_manager),
+            Mode::BatchRename => input_handler::batch_rename_mode(event, &mut file_manager),
+            Mode::SetMark => input_handler::set_mark_mode(event, &mut file_manager),
+            Mode::JumpMark => input_handler::jump_mark_mode(event, &mut file_manager),
+            Mode::Bookmarks => input_handler::bookmarks_mode(event, &mut file_manager),
+            Mode::Jump => input_handler::jump_mode(event, &mut file_manager),
+            Mode::GoTo => input_handler::goto_mode(event, &mut file_manager),
         };
 
-        if let Err(error) = result {
-            terminal.clear()?;
-            disable_raw_mode()?;
-            execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-            break;
+        if file_manager.take_redraw() {
+            terminal.clear().map_err(AppError::Fatal)?;
+        }
+
+        match result {
+            Ok(()) => {}
+            Err(AppError::Quit) => return Ok(()),
+            // ошибка действия показывается в строке состояния, работа продолжается
+            Err(AppError::Recoverable(err)) => file_manager.error(&err),
+            Err(err) => return Err(err),
         }
     }
+}
 
-    Ok(())
+// Ошибки здесь игнорируются: восстановить терминал ещё раз всё равно не получится
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
 }
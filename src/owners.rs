@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::sync::{Mutex, OnceLock};
+
+// Начальный размер буфера для get*_r; для групп с большим числом участников он удваивается
+const BUFFER_SIZE: usize = 4096;
+const MAX_BUFFER_SIZE: usize = 1 << 20;
+
+static USERS: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+static GROUPS: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+
+// Имя пользователя по uid, а если его нет в системе — сам uid
+pub fn user_name(uid: u32) -> String {
+    cached(&USERS, uid, lookup_user)
+}
+
+pub fn group_name(gid: u32) -> String {
+    cached(&GROUPS, gid, lookup_group)
+}
+
+fn cached(
+    cache: &OnceLock<Mutex<HashMap<u32, String>>>,
+    id: u32,
+    lookup: fn(u32) -> Option<String>,
+) -> String {
+    let mut cache = cache
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache
+        .entry(id)
+        .or_insert_with(|| lookup(id).unwrap_or_else(|| id.to_string()))
+        .clone()
+}
+
+fn lookup_user(uid: u32) -> Option<String> {
+    lookup(
+        |passwd: &mut libc::passwd, buffer, result| unsafe {
+            libc::getpwuid_r(uid, passwd, buffer.as_mut_ptr(), buffer.len(), result)
+        },
+        |passwd| {
+            unsafe { CStr::from_ptr(passwd.pw_name) }
+                .to_string_lossy()
+                .into_owned()
+        },
+    )
+}
+
+fn lookup_group(gid: u32) -> Option<String> {
+    lookup(
+        |group: &mut libc::group, buffer, result| unsafe {
+            libc::getgrgid_r(gid, group, buffer.as_mut_ptr(), buffer.len(), result)
+        },
+        |group| {
+            unsafe { CStr::from_ptr(group.gr_name) }
+                .to_string_lossy()
+                .into_owned()
+        },
+    )
+}
+
+// uid по имени пользователя или числу
+pub fn user_id(name: &str) -> Option<u32> {
+    if let Ok(uid) = name.parse() {
+        return Some(uid);
+    }
+    let name = CString::new(name).ok()?;
+    lookup(
+        |passwd: &mut libc::passwd, buffer, result| unsafe {
+            libc::getpwnam_r(
+                name.as_ptr(),
+                passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                result,
+            )
+        },
+        |passwd| passwd.pw_uid,
+    )
+}
+
+pub fn group_id(name: &str) -> Option<u32> {
+    if let Ok(gid) = name.parse() {
+        return Some(gid);
+    }
+    let name = CString::new(name).ok()?;
+    lookup(
+        |group: &mut libc::group, buffer, result| unsafe {
+            libc::getgrnam_r(
+                name.as_ptr(),
+                group,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                result,
+            )
+        },
+        |group| group.gr_gid,
+    )
+}
+
+// Вызывает функцию семейства get*_r, удваивая буфер, пока ей не хватает места (ERANGE).
+// Строки записи указывают в буфер, поэтому нужное из неё читается, пока он жив
+fn lookup<T, R>(
+    call: impl Fn(&mut T, &mut [libc::c_char], &mut *mut T) -> libc::c_int,
+    read: impl FnOnce(&T) -> R,
+) -> Option<R> {
+    let mut size = BUFFER_SIZE;
+    loop {
+        let mut buffer = vec![0 as libc::c_char; size];
+        // passwd и group — структуры из указателей и чисел, нули для них допустимы
+        let mut entry: T = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        match call(&mut entry, &mut buffer, &mut result) {
+            libc::ERANGE if size < MAX_BUFFER_SIZE => size *= 2,
+            0 if !result.is_null() => return Some(read(&entry)),
+            _ => return None,
+        }
+    }
+}
@@ -0,0 +1,483 @@
+use crate::sort::Sort;
+use crate::visibility::Visibility;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, DirEntry};
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Сколько папок помнит история «назад»
+const HISTORY_LIMIT: usize = 100;
+
+// Вид элемента для значка и цвета в списке. Определяется при чтении папки,
+// чтобы отрисовка не обращалась к диску
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Kind {
+    #[default]
+    File,
+    Dir,
+    Link,
+    BrokenLink,
+}
+
+// Состояние одной панели: папка, список, выделение, отметки, фильтр и сортировка
+pub struct Panel {
+    current_dir: PathBuf,
+    all_files: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    kinds: HashMap<PathBuf, Kind>,
+    hidden_count: usize,
+    sorts: HashMap<PathBuf, Sort>,
+    selected: usize,
+    marked: BTreeSet<PathBuf>,
+    visual_anchor: Option<usize>,
+    search_buffer: String,
+    back: Vec<PathBuf>,
+    forward: Vec<PathBuf>,
+    // последний выделенный элемент каждой папки, где уже побывали
+    cursors: HashMap<PathBuf, PathBuf>,
+}
+
+impl Panel {
+    pub fn new(current_dir: PathBuf) -> Self {
+        Self {
+            current_dir,
+            all_files: Vec::new(),
+            files: Vec::new(),
+            kinds: HashMap::new(),
+            hidden_count: 0,
+            sorts: HashMap::new(),
+            selected: 0,
+            marked: BTreeSet::new(),
+            visual_anchor: None,
+            search_buffer: String::new(),
+            back: Vec::new(),
+            forward: Vec::new(),
+            cursors: HashMap::new(),
+        }
+    }
+
+    // Getters
+    pub fn get_current_dir(&self) -> &PathBuf {
+        &self.current_dir
+    }
+
+    pub fn get_files(&self) -> &Vec<PathBuf> {
+        &self.files
+    }
+
+    pub fn get_selected(&self) -> &usize {
+        &self.selected
+    }
+
+    pub fn get_selected_path(&self) -> Option<&PathBuf> {
+        self.files.get(self.selected)
+    }
+
+    pub fn get_kind(&self, path: &Path) -> Kind {
+        self.kinds.get(path).copied().unwrap_or_default()
+    }
+
+    pub fn get_total_count(&self) -> usize {
+        self.all_files.len()
+    }
+
+    pub fn get_hidden_count(&self) -> usize {
+        self.hidden_count
+    }
+
+    pub fn get_search_buffer(&self) -> &String {
+        &self.search_buffer
+    }
+
+    // Содержимое папки без скрытых и игнорируемых элементов
+    fn read_dir(&mut self, dir: &Path, visibility: &mut Visibility) -> io::Result<Vec<PathBuf>> {
+        visibility.prepare(dir);
+        self.kinds = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| (entry.path(), kind_of(&entry)))
+            .collect();
+        let entries: Vec<PathBuf> = self.kinds.keys().cloned().collect();
+        let total = entries.len();
+
+        let files: Vec<PathBuf> = entries
+            .into_iter()
+            .filter(|path| visibility.is_visible(path, path.is_dir()))
+            .collect();
+        self.hidden_count = total - files.len();
+        Ok(self.get_sort_for(dir).apply(files))
+    }
+
+    // Переход в папку: прежняя попадает в историю «назад», история «вперёд» сбрасывается
+    pub fn load_dir(&mut self, dir: PathBuf, visibility: &mut Visibility) -> io::Result<()> {
+        let previous = self.current_dir.clone();
+        self.open_dir(dir, visibility)?;
+        if self.current_dir != previous {
+            self.back.push(previous);
+            if self.back.len() > HISTORY_LIMIT {
+                self.back.remove(0);
+            }
+            self.forward.clear();
+        }
+        Ok(())
+    }
+
+    // Папка, которой больше нет, выпадает из истории, а ошибка возвращается
+    pub fn go_back(&mut self, visibility: &mut Visibility) -> io::Result<bool> {
+        let Some(dir) = self.back.pop() else {
+            return Ok(false);
+        };
+        let current = self.current_dir.clone();
+        self.open_dir(dir, visibility)?;
+        self.forward.push(current);
+        Ok(true)
+    }
+
+    pub fn go_forward(&mut self, visibility: &mut Visibility) -> io::Result<bool> {
+        let Some(dir) = self.forward.pop() else {
+            return Ok(false);
+        };
+        let current = self.current_dir.clone();
+        self.open_dir(dir, visibility)?;
+        self.back.push(current);
+        Ok(true)
+    }
+
+    // Загружает содержимое папки и сбрасывает состояние, привязанное к прежней.
+    // Курсор встаёт туда, где он был при прошлом посещении
+    fn open_dir(&mut self, dir: PathBuf, visibility: &mut Visibility) -> io::Result<()> {
+        let files = self.read_dir(&dir, visibility)?;
+        if let Some(path) = self.get_selected_path().cloned() {
+            self.cursors.insert(self.current_dir.clone(), path);
+        }
+        self.current_dir = dir;
+        self.search_buffer.clear();
+        self.marked.clear();
+        self.visual_anchor = None;
+        self.set_file_list(files);
+        self.selected = 0;
+        if let Some(path) = self.cursors.get(&self.current_dir).cloned() {
+            self.focus(&path);
+        }
+        Ok(())
+    }
+
+    // Перечитывает текущую папку, выделенный элемент остаётся выделенным.
+    // Если папку удалили или переместили, панель переходит в ближайшую существующую выше
+    pub fn refresh(&mut self, visibility: &mut Visibility) -> io::Result<()> {
+        if !self.current_dir.is_dir() {
+            let nearest = self
+                .current_dir
+                .ancestors()
+                .skip(1)
+                .find(|dir| dir.is_dir())
+                .unwrap_or(Path::new("/"))
+                .to_path_buf();
+            return self.open_dir(nearest, visibility);
+        }
+        let selected = self.get_selected_path().cloned();
+        let files = self.read_dir(&self.current_dir.clone(), visibility)?;
+        self.set_file_list(files);
+        if let Some(path) = selected {
+            self.focus(&path);
+        }
+        Ok(())
+    }
+
+    // Navigation
+
+    pub fn up(&mut self) {
+        if self.files.is_empty() {
+            return;
+        }
+        if self.selected > 0 {
+            self.selected -= 1;
+        } else {
+            self.selected = self.files.len() - 1;
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.files.is_empty() {
+            return;
+        }
+        if self.selected + 1 < self.files.len() {
+            self.selected += 1;
+        } else {
+            self.selected = 0
+        }
+    }
+
+    // Выделяет путь, если он есть в списке; иначе курсор остаётся на месте
+    pub fn focus(&mut self, path: &Path) -> bool {
+        match self.files.iter().position(|file| file == path) {
+            Some(index) => {
+                self.selected = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Filter
+
+    // Сужает список файлов по строке поиска, лучшие совпадения сверху
+    fn apply_filter(&mut self) {
+        let query = self.search_buffer.trim();
+        if query.is_empty() {
+            self.files = self.all_files.clone();
+        } else {
+            let matcher = SkimMatcherV2::default();
+            let mut scored: Vec<(i64, &PathBuf)> = self
+                .all_files
+                .iter()
+                .filter_map(|path| {
+                    let name = path.file_name()?.to_string_lossy();
+                    matcher.fuzzy_match(&name, query).map(|score| (score, path))
+                })
+                .collect();
+            scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+            self.files = scored.into_iter().map(|(_, path)| path.clone()).collect();
+        }
+
+        if self.selected >= self.files.len() {
+            self.selected = 0;
+        }
+    }
+
+    fn set_file_list(&mut self, files: Vec<PathBuf>) {
+        self.all_files = files;
+        self.apply_filter();
+    }
+
+    pub fn add_to_search_buffer(&mut self, c: char) {
+        self.search_buffer.push(c);
+        self.apply_filter();
+        self.selected = 0;
+    }
+
+    pub fn delete_from_search_buffer(&mut self) {
+        self.search_buffer.pop();
+        self.apply_filter();
+        self.selected = 0;
+    }
+
+    // Позиции совпавших символов в имени для подсветки
+    pub fn get_filter_indices(&self, name: &str) -> Vec<usize> {
+        let query = self.search_buffer.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        SkimMatcherV2::default()
+            .fuzzy_indices(name, query)
+            .map(|(_, indices)| indices)
+            .unwrap_or_default()
+    }
+
+    pub fn is_filter_active(&self) -> bool {
+        !self.search_buffer.trim().is_empty()
+    }
+
+    pub fn clear_filter(&mut self) {
+        let selected = self.get_selected_path().cloned();
+        self.search_buffer.clear();
+        self.apply_filter();
+        self.selected = 0;
+        if let Some(path) = selected {
+            self.focus(&path);
+        }
+    }
+
+    // Marks
+
+    pub fn is_marked(&self, index: usize) -> bool {
+        if let Some(anchor) = self.visual_anchor {
+            let (start, end) = ordered(anchor, self.selected);
+            if (start..=end).contains(&index) {
+                return true;
+            }
+        }
+        self.files
+            .get(index)
+            .is_some_and(|path| self.marked.contains(path))
+    }
+
+    pub fn get_marked(&self) -> BTreeSet<PathBuf> {
+        let mut marked = self.marked.clone();
+        if let Some(anchor) = self.visual_anchor {
+            let (start, end) = ordered(anchor, self.selected);
+            marked.extend(self.files.iter().skip(start).take(end - start + 1).cloned());
+        }
+        marked
+    }
+
+    pub fn get_marked_count(&self) -> usize {
+        self.get_marked().len()
+    }
+
+    // Отмеченные элементы, а если их нет — выделенный
+    pub fn get_targets(&self) -> Vec<PathBuf> {
+        let marked = self.get_marked();
+        if marked.is_empty() {
+            return self.get_selected_path().cloned().into_iter().collect();
+        }
+        marked.into_iter().collect()
+    }
+
+    pub fn is_visual(&self) -> bool {
+        self.visual_anchor.is_some()
+    }
+
+    // Возвращает false, если отмечать нечего
+    pub fn toggle_mark(&mut self) -> bool {
+        let Some(path) = self.get_selected_path().cloned() else {
+            return false;
+        };
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+        true
+    }
+
+    // Первое нажатие запоминает начало диапазона, второе отмечает его
+    pub fn toggle_visual(&mut self) {
+        match self.visual_anchor.take() {
+            Some(anchor) => {
+                let (start, end) = ordered(anchor, self.selected);
+                let range = self.files.iter().skip(start).take(end - start + 1);
+                self.marked.extend(range.cloned());
+            }
+            None if !self.files.is_empty() => self.visual_anchor = Some(self.selected),
+            None => {}
+        }
+    }
+
+    pub fn cancel_visual(&mut self) {
+        self.visual_anchor = None;
+    }
+
+    pub fn invert_marks(&mut self) {
+        self.visual_anchor = None;
+        for path in &self.files {
+            if !self.marked.remove(path) {
+                self.marked.insert(path.clone());
+            }
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.visual_anchor = None;
+        self.marked.clear();
+    }
+
+    pub fn mark_where(&mut self, predicate: impl Fn(&Path) -> bool) {
+        let found = self.files.iter().filter(|path| predicate(path));
+        self.marked.extend(found.cloned());
+    }
+
+    // Sort
+
+    pub fn get_sort_for(&self, dir: &Path) -> Sort {
+        self.sorts.get(dir).copied().unwrap_or_default()
+    }
+
+    pub fn get_sort(&self) -> Sort {
+        self.get_sort_for(&self.current_dir)
+    }
+
+    // Сортировка запоминается для текущей папки
+    pub fn set_sort(&mut self, sort: Sort, visibility: &mut Visibility) -> io::Result<()> {
+        self.sorts.insert(self.current_dir.clone(), sort);
+        self.refresh(visibility)
+    }
+}
+
+fn ordered(a: usize, b: usize) -> (usize, usize) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+// Вкладка: своя пара панелей, активная панель и прокрутка превью
+pub struct Tab {
+    panels: [Panel; 2],
+    active: usize,
+    file_scroll: usize,
+}
+
+impl Tab {
+    pub fn new(dir: PathBuf, visibility: &mut Visibility) -> io::Result<Self> {
+        let mut tab = Self {
+            panels: [Panel::new(dir.clone()), Panel::new(dir)],
+            active: 0,
+            file_scroll: 0,
+        };
+        for panel in &mut tab.panels {
+            panel.refresh(visibility)?;
+        }
+        Ok(tab)
+    }
+
+    // Getters
+    pub fn get_panels(&self) -> &[Panel; 2] {
+        &self.panels
+    }
+
+    pub fn get_active(&self) -> usize {
+        self.active
+    }
+
+    pub fn get_panel(&self) -> &Panel {
+        &self.panels[self.active]
+    }
+
+    pub fn get_other_panel(&self) -> &Panel {
+        &self.panels[1 - self.active]
+    }
+
+    pub fn get_file_scroll(&self) -> usize {
+        self.file_scroll
+    }
+
+    pub fn panel_mut(&mut self) -> &mut Panel {
+        &mut self.panels[self.active]
+    }
+
+    pub fn panels_mut(&mut self) -> &mut [Panel; 2] {
+        &mut self.panels
+    }
+
+    pub fn switch_panel(&mut self) {
+        self.active = 1 - self.active;
+    }
+
+    pub fn set_file_scroll(&mut self, file_scroll: usize) {
+        self.file_scroll = file_scroll;
+    }
+
+    // Имя папки активной панели для полосы вкладок
+    pub fn title(&self) -> String {
+        let dir = self.get_panel().get_current_dir();
+        dir.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| dir.display().to_string())
+    }
+}
+
+fn kind_of(entry: &DirEntry) -> Kind {
+    match entry.file_type() {
+        // exists() идёт по ссылке, поэтому для битой ссылки вернёт false
+        Ok(file_type) if file_type.is_symlink() => {
+            if entry.path().exists() {
+                Kind::Link
+            } else {
+                Kind::BrokenLink
+            }
+        }
+        Ok(file_type) if file_type.is_dir() => Kind::Dir,
+        _ => Kind::File,
+    }
+}
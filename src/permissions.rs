@@ -0,0 +1,211 @@
+use crate::file_ops::{Attributes, ModeChange};
+use crate::owners;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+// Биты сетки: строки — владелец, группа, остальные; столбцы — r, w, x, особый бит
+pub const GRID: [[u32; 4]; 3] = [
+    [0o400, 0o200, 0o100, 0o4000],
+    [0o040, 0o020, 0o010, 0o2000],
+    [0o004, 0o002, 0o001, 0o1000],
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Bit(u32),
+    Octal,
+    Owner,
+    Group,
+    Recursive,
+    ExecDirsOnly,
+}
+
+// Окно chmod/chown для отмеченных элементов
+pub struct PermissionsDialog {
+    targets: Vec<PathBuf>,
+    mode: u32,
+    // биты, переключённые в сетке; остальные у каждого элемента остаются своими
+    toggled: u32,
+    // восьмеричное число задаёт права целиком
+    octal_edited: bool,
+    octal: String,
+    owner: String,
+    group: String,
+    initial_owner: String,
+    initial_group: String,
+    recursive: bool,
+    exec_dirs_only: bool,
+    selected: usize,
+}
+
+impl PermissionsDialog {
+    // Начальные значения берутся у первого элемента
+    pub fn new(targets: Vec<PathBuf>) -> io::Result<Self> {
+        let first = targets
+            .first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Ничего не выбрано"))?;
+        let metadata = fs::symlink_metadata(first)?;
+        let mode = metadata.mode() & 0o7777;
+        let owner = owners::user_name(metadata.uid());
+        let group = owners::group_name(metadata.gid());
+
+        Ok(Self {
+            targets,
+            mode,
+            toggled: 0,
+            octal_edited: false,
+            octal: format!("{:04o}", mode),
+            initial_owner: owner.clone(),
+            initial_group: group.clone(),
+            owner,
+            group,
+            recursive: false,
+            exec_dirs_only: false,
+            selected: 0,
+        })
+    }
+
+    // Порядок обхода полей клавишами
+    pub fn fields() -> Vec<Field> {
+        let mut fields: Vec<Field> = GRID.iter().flatten().map(|bit| Field::Bit(*bit)).collect();
+        fields.extend([
+            Field::Octal,
+            Field::Owner,
+            Field::Group,
+            Field::Recursive,
+            Field::ExecDirsOnly,
+        ]);
+        fields
+    }
+
+    // Getters
+    pub fn get_targets(&self) -> &Vec<PathBuf> {
+        &self.targets
+    }
+
+    pub fn get_octal(&self) -> &String {
+        &self.octal
+    }
+
+    pub fn get_owner(&self) -> &String {
+        &self.owner
+    }
+
+    pub fn get_group(&self) -> &String {
+        &self.group
+    }
+
+    pub fn is_set(&self, bit: u32) -> bool {
+        self.mode & bit != 0
+    }
+
+    pub fn is_recursive(&self) -> bool {
+        self.recursive
+    }
+
+    pub fn is_exec_dirs_only(&self) -> bool {
+        self.exec_dirs_only
+    }
+
+    pub fn get_field(&self) -> Field {
+        Self::fields()[self.selected]
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % Self::fields().len();
+    }
+
+    pub fn previous(&mut self) {
+        let count = Self::fields().len();
+        self.selected = (self.selected + count - 1) % count;
+    }
+
+    // Пробел переключает флажки, в текстовых полях это обычный символ
+    pub fn toggle(&mut self) {
+        match self.get_field() {
+            Field::Bit(bit) => {
+                self.mode ^= bit;
+                self.toggled ^= bit;
+                self.octal = format!("{:04o}", self.mode);
+            }
+            Field::Recursive => self.recursive = !self.recursive,
+            Field::ExecDirsOnly => self.exec_dirs_only = !self.exec_dirs_only,
+            _ => {}
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        match self.get_field() {
+            Field::Octal if c.is_digit(8) && self.octal.len() < 4 => {
+                self.octal.push(c);
+                self.sync_octal();
+            }
+            Field::Owner => self.owner.push(c),
+            Field::Group => self.group.push(c),
+            _ => {}
+        }
+    }
+
+    pub fn pop(&mut self) {
+        match self.get_field() {
+            Field::Octal => {
+                self.octal.pop();
+                self.sync_octal();
+            }
+            Field::Owner => {
+                self.owner.pop();
+            }
+            Field::Group => {
+                self.group.pop();
+            }
+            _ => {}
+        }
+    }
+
+    // Сетка следует за восьмеричным полем, пока в нём корректное число
+    fn sync_octal(&mut self) {
+        if let Ok(mode) = u32::from_str_radix(&self.octal, 8) {
+            self.mode = mode & 0o7777;
+            self.octal_edited = true;
+        }
+    }
+
+    // Права, владелец и группа меняются, только если их поправили в окне
+    pub fn attributes(&self) -> io::Result<Attributes> {
+        let resolve = |value: &str, initial: &str, lookup: fn(&str) -> Option<u32>| {
+            if value.trim() == initial {
+                return Ok(None);
+            }
+            lookup(value.trim()).map(Some).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Неизвестный пользователь или группа: {}", value),
+                )
+            })
+        };
+
+        let mode = if self.octal_edited {
+            Some(ModeChange {
+                set: self.mode,
+                clear: 0o7777,
+            })
+        } else if self.toggled != 0 {
+            Some(ModeChange {
+                set: self.mode & self.toggled,
+                clear: self.toggled,
+            })
+        } else {
+            None
+        };
+
+        Ok(Attributes {
+            mode,
+            exec_dirs_only: self.exec_dirs_only,
+            owner: resolve(&self.owner, &self.initial_owner, owners::user_id)?,
+            group: resolve(&self.group, &self.initial_group, owners::group_id)?,
+            recursive: self.recursive,
+        })
+    }
+}
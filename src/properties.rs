@@ -0,0 +1,197 @@
+use crate::owners;
+use crate::trash::decode_mount_point;
+use crate::ui::{format_permissions, format_size};
+use chrono::{DateTime, Local, TimeZone};
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+use std::time::SystemTime;
+
+// Сколько байт читать для определения MIME-типа
+const SNIFF_SIZE: usize = 8192;
+// Столько переходов по ссылкам допускает и ядро (ELOOP)
+const MAX_LINKS: usize = 40;
+
+// Свойства в виде пар «название — значение» для окна свойств
+pub fn inspect(path: &Path) -> io::Result<Vec<(&'static str, String)>> {
+    let metadata = fs::symlink_metadata(path)?;
+    let path = std::path::absolute(path)?;
+    let mode = metadata.mode();
+
+    let mut properties = vec![
+        ("Путь", path.display().to_string()),
+        ("Тип", file_kind(&metadata).to_string()),
+        ("MIME", mime_type(&path, &metadata)),
+        (
+            "Размер",
+            format!("{} ({} байт)", format_size(metadata.len()), metadata.len()),
+        ),
+        (
+            "Блоков",
+            format!(
+                "{} по 512 байт ({})",
+                metadata.blocks(),
+                format_size(metadata.blocks() * 512)
+            ),
+        ),
+        ("Inode", metadata.ino().to_string()),
+        ("Жёстких ссылок", metadata.nlink().to_string()),
+        (
+            "Права",
+            format!("{:04o} {}", mode & 0o7777, format_permissions(mode)),
+        ),
+        (
+            "Владелец",
+            format!("{} ({})", owners::user_name(metadata.uid()), metadata.uid()),
+        ),
+        (
+            "Группа",
+            format!(
+                "{} ({})",
+                owners::group_name(metadata.gid()),
+                metadata.gid()
+            ),
+        ),
+        ("Доступ", format_timestamp(metadata.atime())),
+        ("Изменение", format_timestamp(metadata.mtime())),
+        ("Смена атрибутов", format_timestamp(metadata.ctime())),
+        (
+            "Создание",
+            metadata
+                .created()
+                .map(format_system_time)
+                .unwrap_or_else(|_| String::from("неизвестно")),
+        ),
+    ];
+
+    if metadata.file_type().is_symlink() {
+        properties.push(("Цепочка ссылок", symlink_chain(&path)));
+    }
+    let (fs_type, mount_point) = mount_of(&path, &metadata).unwrap_or_default();
+    properties.push(("Файловая система", fs_type));
+    properties.push(("Точка монтирования", mount_point));
+    Ok(properties)
+}
+
+fn file_kind(metadata: &Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        "символическая ссылка"
+    } else if file_type.is_dir() {
+        "папка"
+    } else if file_type.is_file() {
+        "файл"
+    } else if file_type.is_fifo() {
+        "именованный канал"
+    } else if file_type.is_socket() {
+        "сокет"
+    } else if file_type.is_block_device() {
+        "блочное устройство"
+    } else if file_type.is_char_device() {
+        "символьное устройство"
+    } else {
+        "неизвестно"
+    }
+}
+
+// По сигнатуре содержимого; если её нет — текст или двоичные данные
+fn mime_type(path: &Path, metadata: &Metadata) -> String {
+    if metadata.file_type().is_symlink() {
+        return String::from("inode/symlink");
+    }
+    if metadata.is_dir() {
+        return String::from("inode/directory");
+    }
+    if !metadata.is_file() {
+        return String::from("inode/x-special");
+    }
+    if metadata.len() == 0 {
+        return String::from("application/x-empty");
+    }
+
+    let mut buffer = Vec::with_capacity(SNIFF_SIZE);
+    let read =
+        File::open(path).and_then(|file| file.take(SNIFF_SIZE as u64).read_to_end(&mut buffer));
+    if read.is_err() {
+        return String::from("неизвестно (нет доступа)");
+    }
+    if let Some(kind) = infer::get(&buffer) {
+        return kind.mime_type().to_string();
+    }
+    if buffer.contains(&0) {
+        String::from("application/octet-stream")
+    } else {
+        String::from("text/plain")
+    }
+}
+
+// path → цель → цель цели ..., с пометкой о битой или зацикленной ссылке
+fn symlink_chain(path: &Path) -> String {
+    let mut chain = vec![path.display().to_string()];
+    let mut current = path.to_path_buf();
+    let mut visited = vec![current.clone()];
+
+    for _ in 0..MAX_LINKS {
+        let Ok(target) = fs::read_link(&current) else {
+            return chain.join(" → ");
+        };
+        chain.push(target.display().to_string());
+        current = current
+            .parent()
+            .map_or_else(|| target.clone(), |parent| parent.join(&target));
+
+        if visited.contains(&current) {
+            chain.push(String::from("(цикл)"));
+            return chain.join(" → ");
+        }
+        visited.push(current.clone());
+
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {}
+            Ok(_) => return chain.join(" → "),
+            Err(_) => {
+                chain.push(String::from("(не существует)"));
+                return chain.join(" → ");
+            }
+        }
+    }
+    chain.push(String::from("(слишком много ссылок)"));
+    chain.join(" → ")
+}
+
+// Тип файловой системы и точка монтирования: самый длинный подходящий путь из /proc/self/mounts
+// Ссылка лежит там же, где её папка, поэтому для неё ищется папка
+fn mount_of(path: &Path, metadata: &Metadata) -> Option<(String, String)> {
+    let path = if metadata.file_type().is_symlink() {
+        fs::canonicalize(path.parent()?).ok()?
+    } else {
+        fs::canonicalize(path).ok()?
+    };
+    let mounts = fs::read_to_string("/proc/self/mounts").ok()?;
+
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = decode_mount_point(fields.nth(1)?);
+            let fs_type = fields.next()?.to_string();
+            Some((fs_type, mount_point))
+        })
+        .filter(|(_, mount_point)| path.starts_with(mount_point))
+        .max_by_key(|(_, mount_point)| mount_point.len())
+}
+
+fn format_timestamp(seconds: i64) -> String {
+    Local
+        .timestamp_opt(seconds, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn format_system_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
@@ -0,0 +1,291 @@
+use crate::file_ops;
+use crossterm::{
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Редактор, если не заданы ни $VISUAL, ни $EDITOR
+const DEFAULT_EDITOR: &str = "vi";
+
+// Имена папки открываются во внешнем редакторе по одному на строку,
+// возвращается то, что пользователь сохранил
+pub fn edit_names(paths: &[PathBuf]) -> io::Result<Vec<String>> {
+    let names: Vec<String> = paths.iter().map(|path| name(path)).collect();
+    if let Some(bad) = names.iter().find(|name| name.contains('\n')) {
+        return Err(invalid(format!(
+            "Имя с переводом строки нельзя переименовать списком: {:?}",
+            bad
+        )));
+    }
+
+    let file = create_temp_file(&(names.join("\n") + "\n"))?;
+    let result = run_editor(&file).and_then(|_| fs::read_to_string(&file));
+    let _ = fs::remove_file(&file);
+
+    let text = result?;
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    // редакторы часто добавляют пустые строки в конец
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    Ok(lines)
+}
+
+// Временный файл доступен только владельцу. create_new не идёт по чужой ссылке
+// и не открывает подложенный заранее файл, а при совпадении имени пробуется другое
+fn create_temp_file(content: &str) -> io::Result<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.subsec_nanos());
+    for attempt in 0..100 {
+        let file = env::temp_dir().join(format!(
+            "cli_file_manager-rename-{}-{}-{}.txt",
+            process::id(),
+            nanos,
+            attempt
+        ));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&file)
+        {
+            Ok(mut handle) => {
+                if let Err(err) = handle.write_all(content.as_bytes()) {
+                    let _ = fs::remove_file(&file);
+                    return Err(err);
+                }
+                return Ok(file);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "Не удалось создать временный файл для имён",
+    ))
+}
+
+// Программа уступает терминал редактору и забирает его обратно, даже если редактор не запустился
+fn run_editor(file: &Path) -> io::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    // в переменной могут быть аргументы, например "code --wait"
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(DEFAULT_EDITOR);
+
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+    let status = Command::new(program).args(words).arg(file).status();
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+
+    let status = status?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} завершился с ошибкой, имена не изменены",
+            program
+        )));
+    }
+    Ok(())
+}
+
+// Сопоставляет строки из редактора с исходными путями; неизменённые имена пропускаются
+pub fn pair_names(paths: &[PathBuf], lines: &[String]) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    if lines.len() != paths.len() {
+        return Err(invalid(format!(
+            "Было строк: {}, стало: {}. Удалять и добавлять строки нельзя",
+            paths.len(),
+            lines.len()
+        )));
+    }
+
+    let mut renames = Vec::new();
+    for (number, (path, line)) in paths.iter().zip(lines).enumerate() {
+        let new_name = line.trim_end_matches('\r');
+        if new_name.trim().is_empty() {
+            return Err(invalid(format!("Строка {}: пустое имя", number + 1)));
+        }
+        if new_name.contains('/') || new_name == "." || new_name == ".." {
+            return Err(invalid(format!(
+                "Строка {}: недопустимое имя {}",
+                number + 1,
+                new_name
+            )));
+        }
+        if name(path) != new_name {
+            renames.push((path.clone(), path.with_file_name(new_name)));
+        }
+    }
+    Ok(renames)
+}
+
+// Порядок переименований, при котором ничего не затирается.
+// Циклы (a → b, b → a) разрываются через временное имя
+pub fn plan(renames: Vec<(PathBuf, PathBuf)>) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let sources: HashSet<&PathBuf> = renames.iter().map(|(from, _)| from).collect();
+    let mut targets = HashSet::new();
+    for (from, to) in &renames {
+        if !targets.insert(to) {
+            return Err(collision(format!(
+                "Несколько элементов получают имя {}",
+                name(to)
+            )));
+        }
+        // занятое имя допустимо, только если его владелец сам переименовывается
+        if file_ops::exists(to) && !sources.contains(to) {
+            return Err(collision(format!(
+                "{} → {}: такое имя уже есть в папке",
+                name(from),
+                name(to)
+            )));
+        }
+    }
+
+    let mut pending = renames.clone();
+    let mut steps = Vec::new();
+    let mut taken: Vec<PathBuf> = renames.iter().map(|(_, to)| to.clone()).collect();
+    while !pending.is_empty() {
+        // можно выполнить то, чья цель не занята ещё не переименованным элементом
+        let ready = pending
+            .iter()
+            .position(|(_, to)| !pending.iter().any(|(from, _)| from == to));
+        match ready {
+            Some(index) => steps.push(pending.remove(index)),
+            None => {
+                let (from, to) = pending.remove(0);
+                let temporary = file_ops::unique_name(&from, &taken);
+                taken.push(temporary.clone());
+                steps.push((from, temporary.clone()));
+                pending.push((temporary, to));
+            }
+        }
+    }
+    Ok(steps)
+}
+
+// Выполняет шаги по порядку; возвращает сделанные, даже если дальше случилась ошибка
+pub fn apply(steps: Vec<(PathBuf, PathBuf)>) -> (Vec<(PathBuf, PathBuf)>, io::Result<()>) {
+    let mut done = Vec::new();
+    for (from, to) in steps {
+        // имя могло занять что-то другое уже после проверки в plan
+        if let Err(err) = file_ops::rename_noreplace(&from, &to) {
+            return (done, Err(err));
+        }
+        done.push((from, to));
+    }
+    (done, Ok(()))
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn invalid(text: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, text)
+}
+
+fn collision(text: String) -> io::Error {
+    io::Error::new(io::ErrorKind::AlreadyExists, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Папка с файлами, содержимое которых совпадает с их именем
+    fn scratch(test: &str, names: &[&str]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("cli_file_manager-test-{}-{}", process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        for name in names {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        dir
+    }
+
+    fn pairs(dir: &Path, renames: &[(&str, &str)]) -> Vec<(PathBuf, PathBuf)> {
+        renames
+            .iter()
+            .map(|(from, to)| (dir.join(from), dir.join(to)))
+            .collect()
+    }
+
+    fn content(dir: &Path, name: &str) -> String {
+        fs::read_to_string(dir.join(name)).unwrap()
+    }
+
+    #[test]
+    fn swap_goes_through_temporary_name() {
+        let dir = scratch("swap", &["a", "b"]);
+        let steps = plan(pairs(&dir, &[("a", "b"), ("b", "a")])).unwrap();
+        assert_eq!(steps.len(), 3);
+
+        let (done, result) = apply(steps);
+        result.unwrap();
+        assert_eq!(done.len(), 3);
+        assert_eq!(content(&dir, "a"), "b");
+        assert_eq!(content(&dir, "b"), "a");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn three_cycle_rotates_names() {
+        let dir = scratch("cycle", &["a", "b", "c"]);
+        let steps = plan(pairs(&dir, &[("a", "b"), ("b", "c"), ("c", "a")])).unwrap();
+        assert_eq!(steps.len(), 4);
+
+        let (_, result) = apply(steps);
+        result.unwrap();
+        assert_eq!(content(&dir, "b"), "a");
+        assert_eq!(content(&dir, "c"), "b");
+        assert_eq!(content(&dir, "a"), "c");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chain_frees_target_first() {
+        let dir = scratch("chain", &["a", "b"]);
+        let steps = plan(pairs(&dir, &[("a", "b"), ("b", "c")])).unwrap();
+        assert_eq!(steps, pairs(&dir, &[("b", "c"), ("a", "b")]));
+
+        let (_, result) = apply(steps);
+        result.unwrap();
+        assert_eq!(content(&dir, "b"), "a");
+        assert_eq!(content(&dir, "c"), "b");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn same_target_twice_is_rejected() {
+        let dir = scratch("duplicate", &["a", "b"]);
+        let err = plan(pairs(&dir, &[("a", "c"), ("b", "c")])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn existing_name_that_stays_is_rejected() {
+        let dir = scratch("taken", &["a", "b"]);
+        let err = plan(pairs(&dir, &[("a", "b")])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(content(&dir, "b"), "b");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,465 @@
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use regex::Regex;
+use std::fs::{self, DirEntry, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+const BATCH_SIZE: usize = 256;
+const MAX_RESULTS: usize = 1000;
+const BINARY_CHECK_SIZE: usize = 8192;
+const MAX_SNIPPET_LEN: usize = 200;
+
+// Обходит дерево каталогов, пока visit возвращает true
+fn walk(root: PathBuf, cancel: &AtomicBool, mut visit: impl FnMut(&DirEntry) -> bool) {
+    let mut stack = vec![root];
+
+    while let Some(dir) = stack.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            // symlink на каталог не раскрываем, чтобы не зациклиться
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                stack.push(entry.path());
+            }
+            if !visit(&entry) {
+                return;
+            }
+        }
+    }
+}
+
+// Обходит дерево каталогов в отдельном потоке и отдаёт пути пачками
+pub fn spawn_walker(root: PathBuf, cancel: Arc<AtomicBool>) -> Receiver<Vec<PathBuf>> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut connected = true;
+
+        walk(root, &cancel, |entry| {
+            batch.push(entry.path());
+            if batch.len() >= BATCH_SIZE {
+                connected = sender.send(std::mem::take(&mut batch)).is_ok();
+            }
+            connected
+        });
+
+        if connected && !batch.is_empty() {
+            let _ = sender.send(batch);
+        }
+    });
+
+    receiver
+}
+
+pub struct RecursiveSearch {
+    root: PathBuf,
+    query: String,
+    entries: Vec<PathBuf>,
+    results: Vec<(i64, usize)>,
+    selected: usize,
+    receiver: Option<Receiver<Vec<PathBuf>>>,
+    cancel: Arc<AtomicBool>,
+    matcher: SkimMatcherV2,
+}
+
+impl RecursiveSearch {
+    pub fn new(root: PathBuf) -> Self {
+        let mut search = Self {
+            root: PathBuf::new(),
+            query: String::new(),
+            entries: Vec::new(),
+            results: Vec::new(),
+            selected: 0,
+            receiver: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            matcher: SkimMatcherV2::default(),
+        };
+        search.set_root(root);
+        search
+    }
+
+    // Getters
+    pub fn get_root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    pub fn get_query(&self) -> &String {
+        &self.query
+    }
+
+    pub fn get_selected(&self) -> &usize {
+        &self.selected
+    }
+
+    pub fn get_results(&self) -> Vec<&PathBuf> {
+        self.results
+            .iter()
+            .map(|(_, index)| &self.entries[*index])
+            .collect()
+    }
+
+    pub fn get_selected_path(&self) -> Option<&PathBuf> {
+        self.results
+            .get(self.selected)
+            .map(|(_, index)| &self.entries[*index])
+    }
+
+    pub fn get_scanned_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    pub fn set_root(&mut self, root: PathBuf) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.entries.clear();
+        self.results.clear();
+        self.selected = 0;
+        self.receiver = Some(spawn_walker(root.clone(), self.cancel.clone()));
+        self.root = root;
+    }
+
+    pub fn root_to_parent(&mut self) {
+        if let Some(parent) = self.root.parent() {
+            self.set_root(parent.to_path_buf());
+        }
+    }
+
+    pub fn add_to_query(&mut self, c: char) {
+        self.query.push(c);
+        self.rerank();
+    }
+
+    pub fn delete_from_query(&mut self) {
+        self.query.pop();
+        self.rerank();
+    }
+
+    // Забирает новые пути из потока обхода
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else {
+            return;
+        };
+
+        let mut received = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(batch) => received.extend(batch),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    break;
+                }
+            }
+        }
+
+        for path in received {
+            self.entries.push(path);
+            self.rank(self.entries.len() - 1);
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        } else {
+            self.selected = self.results.len().saturating_sub(1);
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.results.len() {
+            self.selected += 1;
+        } else {
+            self.selected = 0;
+        }
+    }
+
+    fn rerank(&mut self) {
+        self.results.clear();
+        self.selected = 0;
+        for index in 0..self.entries.len() {
+            self.rank(index);
+        }
+    }
+
+    fn rank(&mut self, index: usize) {
+        let haystack = relative_name(&self.root, &self.entries[index]);
+        let score = if self.query.is_empty() {
+            Some(0)
+        } else {
+            self.matcher.fuzzy_match(&haystack, &self.query)
+        };
+
+        if let Some(score) = score {
+            let len = self.entries[index].as_os_str().len();
+            let entries = &self.entries;
+            // лучшие совпадения выше, при равном счёте — более короткий путь
+            let position = self.results.partition_point(|(other, other_index)| {
+                *other > score
+                    || (*other == score && entries[*other_index].as_os_str().len() <= len)
+            });
+            if position < MAX_RESULTS {
+                self.results.insert(position, (score, index));
+                self.results.truncate(MAX_RESULTS);
+            }
+        }
+    }
+}
+
+impl Drop for RecursiveSearch {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+pub struct ContentMatch {
+    pub path: PathBuf,
+    pub line: usize,
+    pub snippet: String,
+}
+
+pub struct ContentSearch {
+    root: PathBuf,
+    pattern: String,
+    is_regex: bool,
+    regex: Option<Regex>,
+    error: Option<String>,
+    results: Vec<ContentMatch>,
+    selected: usize,
+    receiver: Option<Receiver<Vec<ContentMatch>>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ContentSearch {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            pattern: String::new(),
+            is_regex: false,
+            regex: None,
+            error: None,
+            results: Vec::new(),
+            selected: 0,
+            receiver: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // Getters
+    pub fn get_root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    pub fn get_pattern(&self) -> &String {
+        &self.pattern
+    }
+
+    pub fn get_results(&self) -> &Vec<ContentMatch> {
+        &self.results
+    }
+
+    pub fn get_selected(&self) -> &usize {
+        &self.selected
+    }
+
+    pub fn get_selected_match(&self) -> Option<&ContentMatch> {
+        self.results.get(self.selected)
+    }
+
+    pub fn get_regex(&self) -> &Option<Regex> {
+        &self.regex
+    }
+
+    pub fn get_error(&self) -> &Option<String> {
+        &self.error
+    }
+
+    pub fn is_regex(&self) -> bool {
+        self.is_regex
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    // Шаблон изменился после последнего запуска поиска
+    pub fn is_dirty(&self) -> bool {
+        self.regex
+            .as_ref()
+            .is_none_or(|regex| regex.as_str() != self.build_pattern())
+    }
+
+    pub fn add_to_pattern(&mut self, c: char) {
+        self.pattern.push(c);
+    }
+
+    pub fn delete_from_pattern(&mut self) {
+        self.pattern.pop();
+    }
+
+    pub fn toggle_regex(&mut self) {
+        self.is_regex = !self.is_regex;
+    }
+
+    pub fn start(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.results.clear();
+        self.selected = 0;
+        self.receiver = None;
+        self.error = None;
+
+        if self.pattern.is_empty() {
+            self.regex = None;
+            return;
+        }
+
+        match Regex::new(&self.build_pattern()) {
+            Ok(regex) => {
+                self.receiver = Some(spawn_grep(
+                    self.root.clone(),
+                    regex.clone(),
+                    self.cancel.clone(),
+                ));
+                self.regex = Some(regex);
+            }
+            Err(err) => {
+                self.regex = None;
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else {
+            return;
+        };
+
+        loop {
+            match receiver.try_recv() {
+                Ok(batch) => self.results.extend(batch),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    break;
+                }
+            }
+        }
+
+        if self.results.len() >= MAX_RESULTS {
+            self.results.truncate(MAX_RESULTS);
+            self.cancel.store(true, Ordering::Relaxed);
+            self.receiver = None;
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        } else {
+            self.selected = self.results.len().saturating_sub(1);
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.results.len() {
+            self.selected += 1;
+        } else {
+            self.selected = 0;
+        }
+    }
+
+    fn build_pattern(&self) -> String {
+        if self.is_regex {
+            self.pattern.clone()
+        } else {
+            regex::escape(&self.pattern)
+        }
+    }
+}
+
+impl Drop for ContentSearch {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn spawn_grep(root: PathBuf, regex: Regex, cancel: Arc<AtomicBool>) -> Receiver<Vec<ContentMatch>> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        walk(root, &cancel, |entry| {
+            if !entry.file_type().is_ok_and(|t| t.is_file()) {
+                return true;
+            }
+            let matches = grep_file(&entry.path(), &regex, &cancel);
+            matches.is_empty() || sender.send(matches).is_ok()
+        });
+    });
+
+    receiver
+}
+
+fn grep_file(path: &Path, regex: &Regex, cancel: &AtomicBool) -> Vec<ContentMatch> {
+    let mut matches = Vec::new();
+    let Ok(file) = File::open(path) else {
+        return matches;
+    };
+    let mut reader = BufReader::new(file);
+
+    if is_binary(&mut reader) {
+        return matches;
+    }
+
+    let mut buffer = Vec::new();
+    let mut line = 0;
+    loop {
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => line += 1,
+        }
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let text = String::from_utf8_lossy(&buffer);
+        if regex.is_match(&text) {
+            matches.push(ContentMatch {
+                path: path.to_path_buf(),
+                line,
+                snippet: text.trim().chars().take(MAX_SNIPPET_LEN).collect(),
+            });
+        }
+    }
+    matches
+}
+
+// Файл считается бинарным, если в начале есть нулевой байт
+fn is_binary(reader: &mut BufReader<File>) -> bool {
+    match reader.fill_buf() {
+        Ok(head) => head[..head.len().min(BINARY_CHECK_SIZE)].contains(&0),
+        Err(_) => true,
+    }
+}
+
+pub fn relative_name(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+use std::fs::{self, Metadata};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Name,
+    Natural,
+    Size,
+    Modified,
+    Created,
+    Extension,
+    Type,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Sort {
+    pub key: SortKey,
+    pub reverse: bool,
+    pub dirs_first: bool,
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Self {
+            key: SortKey::Natural,
+            reverse: false,
+            dirs_first: true,
+        }
+    }
+}
+
+// Всё, что нужно для сравнения, читается с диска один раз
+struct Entry {
+    path: PathBuf,
+    name: String,
+    is_dir: bool,
+    kind: u8,
+    size: u64,
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
+}
+
+impl Entry {
+    fn new(path: PathBuf) -> Self {
+        let link = fs::symlink_metadata(&path).ok();
+        // для ссылок размер и время берутся у цели, если она существует
+        let metadata = fs::metadata(&path).ok().or_else(|| link.clone());
+        let is_dir = metadata.as_ref().is_some_and(Metadata::is_dir);
+        let kind = match &link {
+            Some(link) if link.file_type().is_symlink() => 1,
+            _ if is_dir => 0,
+            Some(link) if link.is_file() => 2,
+            _ => 3,
+        };
+        Self {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            is_dir,
+            kind,
+            size: metadata
+                .as_ref()
+                .filter(|metadata| !metadata.is_dir())
+                .map_or(0, Metadata::len),
+            modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+            created: metadata.as_ref().and_then(|m| m.created().ok()),
+            path,
+        }
+    }
+
+    fn extension(&self) -> &str {
+        match self.name.rfind('.') {
+            Some(dot) if dot > 0 => &self.name[dot + 1..],
+            _ => "",
+        }
+    }
+}
+
+impl Sort {
+    pub fn label(&self) -> String {
+        let key = match self.key {
+            SortKey::Name => "имя",
+            SortKey::Natural => "имя 1..10",
+            SortKey::Size => "размер",
+            SortKey::Modified => "изменён",
+            SortKey::Created => "создан",
+            SortKey::Extension => "расширение",
+            SortKey::Type => "тип",
+        };
+        format!("{} {}", key, if self.reverse { "↓" } else { "↑" })
+    }
+
+    pub fn apply(&self, files: Vec<PathBuf>) -> Vec<PathBuf> {
+        let mut entries: Vec<Entry> = files.into_iter().map(Entry::new).collect();
+        entries.sort_by(|a, b| self.compare(a, b));
+        entries.into_iter().map(|entry| entry.path).collect()
+    }
+
+    fn compare(&self, a: &Entry, b: &Entry) -> Ordering {
+        let by_key = match self.key {
+            SortKey::Name => a.name.cmp(&b.name),
+            SortKey::Natural => Ordering::Equal,
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+            SortKey::Created => a.created.cmp(&b.created),
+            SortKey::Extension => natural_cmp(a.extension(), b.extension()),
+            SortKey::Type => a.kind.cmp(&b.kind),
+        };
+        let mut ordering = by_key.then_with(|| natural_cmp(&a.name, &b.name));
+        if self.reverse {
+            ordering = ordering.reverse();
+        }
+        // папки остаются сверху и при обратном порядке
+        if self.dirs_first {
+            ordering = b.is_dir.cmp(&a.is_dir).then(ordering);
+        }
+        ordering
+    }
+}
+
+// Сравнение без учёта регистра, где числа сравниваются по значению: file2 < file10
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    compare_chunks(a, b, true).then_with(|| compare_chunks(a, b, false))
+}
+
+fn compare_chunks(a: &str, b: &str, ignore_case: bool) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                let x_digits = x.trim_start_matches('0');
+                let y_digits = y.trim_start_matches('0');
+                let ordering = x_digits
+                    .len()
+                    .cmp(&y_digits.len())
+                    .then_with(|| x_digits.cmp(y_digits))
+                    .then_with(|| x.len().cmp(&y.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = if ignore_case {
+                    x.to_lowercase().cmp(y.to_lowercase())
+                } else {
+                    x.cmp(&y)
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        number.push(c);
+    }
+    number
+}
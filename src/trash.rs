@@ -0,0 +1,276 @@
+use crate::config;
+use crate::file_ops;
+use chrono::Local;
+use std::ffi::OsStr;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+// Корзина по спецификации freedesktop.org Trash
+const INFO_EXTENSION: &str = "trashinfo";
+const STICKY_BIT: u32 = 0o1000;
+
+#[derive(Clone)]
+pub struct TrashEntry {
+    pub original: PathBuf,
+    pub deleted: String,
+    pub file: PathBuf,
+    pub info: PathBuf,
+}
+
+pub fn home_trash() -> Option<PathBuf> {
+    Some(config::xdg_dir("XDG_DATA_HOME", ".local/share")?.join("Trash"))
+}
+
+fn uid() -> u32 {
+    unsafe { libc::getuid() }
+}
+
+// Корзина для файла: домашняя, если файл на том же разделе, иначе в корне раздела
+fn trash_dir_for(path: &Path) -> io::Result<PathBuf> {
+    let device = fs::symlink_metadata(path)?.dev();
+
+    if let Some(home) = home_trash() {
+        fs::create_dir_all(&home)?;
+        if fs::metadata(&home)?.dev() == device {
+            return Ok(home);
+        }
+    }
+
+    let top = top_dir(path, device);
+    let shared = top.join(".Trash");
+    if let Ok(metadata) = fs::symlink_metadata(&shared) {
+        let valid = metadata.is_dir() && metadata.permissions().mode() & STICKY_BIT != 0;
+        if valid {
+            let dir = shared.join(uid().to_string());
+            if fs::create_dir_all(&dir).is_ok() {
+                return Ok(dir);
+            }
+        }
+    }
+    let own = top.join(format!(".Trash-{}", uid()));
+    fs::create_dir_all(&own)?;
+    Ok(own)
+}
+
+// Самый верхний каталог на том же устройстве, что и путь
+fn top_dir(path: &Path, device: u64) -> PathBuf {
+    let mut top = path.parent().unwrap_or(path).to_path_buf();
+    while let Some(parent) = top.parent() {
+        match fs::metadata(parent) {
+            Ok(metadata) if metadata.dev() == device => top = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    top
+}
+
+pub fn trash(path: &Path) -> io::Result<TrashEntry> {
+    let path = std::path::absolute(path)?;
+    let trash_dir = trash_dir_for(&path)?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    fs::create_dir_all(&files_dir)?;
+    fs::create_dir_all(&info_dir)?;
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Нельзя удалить корень"))?
+        .to_string_lossy()
+        .into_owned();
+
+    // .trashinfo создаётся атомарно и резервирует имя в корзине
+    let mut counter = 0;
+    let (mut info_file, info_path, trash_name) = loop {
+        let candidate = if counter == 0 {
+            name.clone()
+        } else {
+            format!("{}.{}", name, counter)
+        };
+        let info_path = info_dir.join(format!("{}.{}", candidate, INFO_EXTENSION));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) if !files_dir.join(&candidate).exists() => {
+                break (file, info_path, candidate);
+            }
+            Ok(_) => {
+                fs::remove_file(&info_path)?;
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err),
+        }
+        counter += 1;
+    };
+
+    let deleted = Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(path.as_os_str()),
+        deleted
+    );
+    let file = files_dir.join(&trash_name);
+    let result = info_file
+        .write_all(info.as_bytes())
+        .and_then(|_| fs::rename(&path, &file));
+    if let Err(err) = result {
+        let _ = fs::remove_file(&info_path);
+        return Err(err);
+    }
+
+    Ok(TrashEntry {
+        original: path,
+        deleted,
+        file,
+        info: info_path,
+    })
+}
+
+pub fn list() -> Vec<TrashEntry> {
+    let mut entries: Vec<TrashEntry> = trash_dirs().iter().flat_map(|dir| list_dir(dir)).collect();
+    entries.sort_by(|a, b| b.deleted.cmp(&a.deleted));
+    entries
+}
+
+// Занятое за это время исходное имя не перезаписывается
+pub fn restore(entry: &TrashEntry) -> io::Result<()> {
+    if let Some(parent) = entry.original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    file_ops::rename_noreplace(&entry.file, &entry.original)?;
+    fs::remove_file(&entry.info)
+}
+
+// Домашняя корзина и корзины в корне каждого смонтированного раздела
+fn trash_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = home_trash().into_iter().collect();
+    let uid = uid().to_string();
+
+    let mounts = fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    for mount_point in mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+    {
+        let top = PathBuf::from(decode_mount_point(mount_point));
+        for dir in [
+            top.join(".Trash").join(&uid),
+            top.join(format!(".Trash-{}", uid)),
+        ] {
+            if dir.join("info").is_dir() && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
+fn list_dir(trash_dir: &Path) -> Vec<TrashEntry> {
+    let top = trash_dir
+        .parent()
+        .map(|parent| {
+            // для $top/.Trash/$uid корнем раздела считается родитель .Trash
+            if parent.file_name() == Some(OsStr::new(".Trash")) {
+                parent.parent().unwrap_or(parent)
+            } else {
+                parent
+            }
+        })
+        .unwrap_or(trash_dir)
+        .to_path_buf();
+
+    let Ok(infos) = fs::read_dir(trash_dir.join("info")) else {
+        return Vec::new();
+    };
+
+    infos
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let info = entry.path();
+            if info.extension() != Some(OsStr::new(INFO_EXTENSION)) {
+                return None;
+            }
+            let content = fs::read_to_string(&info).ok()?;
+            let mut original = None;
+            let mut deleted = String::new();
+            for line in content.lines() {
+                if let Some(value) = line.strip_prefix("Path=") {
+                    original = Some(decode_path(value));
+                } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+                    deleted = value.to_string();
+                }
+            }
+            let original = original?;
+            let name = info.file_stem()?;
+            Some(TrashEntry {
+                original: if original.is_absolute() {
+                    original
+                } else {
+                    top.join(original)
+                },
+                deleted,
+                file: trash_dir.join("files").join(name),
+                info,
+            })
+        })
+        .collect()
+}
+
+fn encode_path(path: &OsStr) -> String {
+    let mut encoded = String::new();
+    for byte in path.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(*byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn decode_path(value: &str) -> PathBuf {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(OsStr::from_bytes(&decoded))
+}
+
+// В /proc/mounts пробелы и спецсимволы записаны как \040 и т.п.
+pub fn decode_mount_point(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let code: String = chars.by_ref().take(3).collect();
+            if let Ok(byte) = u8::from_str_radix(&code, 8) {
+                decoded.push(byte as char);
+                continue;
+            }
+            decoded.push(c);
+            decoded.push_str(&code);
+        } else {
+            decoded.push(c);
+        }
+    }
+    decoded
+}
@@ -1,54 +1,101 @@
-use crate::app::{FileManager, InputMode, Mode};
+use crate::app::{Confirm, FileManager, InputMode, Level, Mode};
+use crate::batch_rename::{self, BatchRenameDialog};
+use crate::goto::GoTo;
+use crate::jobs::{Job, JobState};
+use crate::owners;
+use crate::panel::{Kind, Panel};
+use crate::permissions::{Field, PermissionsDialog, GRID};
+use crate::search::relative_name;
+use crate::sort::Sort;
+use crate::view::{Column, ColumnState};
+use chrono::{DateTime, Local};
+use regex::Regex;
+use std::fs::{self, Metadata};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+use std::time::Duration;
 use tui::backend::Backend;
-use tui::layout::{Constraint, Direction, Layout};
-use tui::style::{Color, Style};
-use tui::text::Span;
-use tui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans, Text};
+use tui::widgets::{
+    Block, Borders, Cell, Clear, Gauge, List, ListItem, ListState, Paragraph, Row, Table,
+    TableState, Tabs, Wrap,
+};
 use tui::Frame;
 
+// Сколько задач помещается в панели
+const MAX_VISIBLE_JOBS: usize = 5;
+// Сколько последних операций показывать в истории
+const HISTORY_SIZE: usize = 10;
+
 pub fn render<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager) {
-    let chunks = Layout::default()
+    let jobs = file_manager.get_jobs();
+    let jobs_height = if jobs.is_empty() {
+        0
+    } else {
+        jobs.get_jobs().len().min(MAX_VISIBLE_JOBS) as u16 + 2
+    };
+    let root = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
-        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(jobs_height),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
         .split(f.size());
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .split(root[1]);
 
-    let mut state = ListState::default();
-    state.select(Some(*file_manager.get_selected()));
-
-    let items: Vec<ListItem> = file_manager
-        .get_files()
-        .iter()
-        .enumerate()
-        .map(|(i, path)| {
-            let style = if i == *file_manager.get_selected() {
-                Style::default().fg(Color::White)
-            } else {
-                Style::default()
-            };
-            let display_name = if path.is_dir() {
-                format!("📁 {}", path.file_name().unwrap().to_string_lossy())
-            } else {
-                format!("📄 {}", path.file_name().unwrap().to_string_lossy())
-            };
-            ListItem::new(Span::styled(display_name, style))
-        })
-        .collect();
-
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Files"))
-        .highlight_style(Style::default().bg(Color::Yellow))
-        .highlight_symbol("|-> ");
+    render_tabs(f, file_manager, root[0]);
+    if !jobs.is_empty() {
+        render_jobs(f, file_manager, root[2]);
+    }
+    render_status(f, file_manager, root[3]);
 
-    f.render_widget(Clear, chunks[0]);
-    f.render_stateful_widget(list, chunks[0], &mut state);
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[0]);
+    for (index, area) in halves.iter().enumerate() {
+        render_panel(f, file_manager, index, *area);
+    }
 
     match file_manager.get_mode() {
-        Mode::Normal => {
+        Mode::Normal
+        | Mode::Jobs
+        | Mode::Confirm
+        | Mode::History
+        | Mode::Properties
+        | Mode::Permissions
+        | Mode::SetMark
+        | Mode::JumpMark
+        | Mode::Bookmarks => {
             if let Some(content) = &file_manager.get_content() {
-                let paragraph = Paragraph::new(content.as_ref())
-                    .block(Block::default().borders(Borders::ALL).title("File"))
-                    .scroll((*file_manager.get_file_scroll() as u16, 0));
+                // берутся только видимые строки: прокрутка Paragraph ограничена u16,
+                // а подсвечивать весь длинный файл на каждой отрисовке незачем
+                let skip = *file_manager.get_file_scroll();
+                let height = chunks[1].height.saturating_sub(2) as usize;
+                let text = match file_manager.get_content_highlight() {
+                    Some((line, regex)) => highlight_content(content, *line, regex, skip, height),
+                    None => Text::from(
+                        content
+                            .lines()
+                            .skip(skip)
+                            .take(height)
+                            .map(Spans::from)
+                            .collect::<Vec<Spans>>(),
+                    ),
+                };
+                let paragraph = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::ALL).title("File"));
                 f.render_widget(Clear, chunks[1]);
                 f.render_widget(paragraph, chunks[1]);
             } else {
@@ -63,7 +110,7 @@ pub fn render<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager) {
                 let input = Paragraph::new(file_manager.get_input_buffer().as_ref()).block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .title("Введите имя: "),
+                        .title(file_manager.get_input_title()),
                 );
                 f.render_widget(input, chunks[1]);
             }
@@ -88,12 +135,257 @@ pub fn render<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager) {
             }
         },
         Mode::Search => {
-            let input = Paragraph::new(file_manager.get_search_buffer().as_ref()).block(
+            let input = Paragraph::new(file_manager.get_panel().get_search_buffer().as_ref())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Введите имя: "),
+                );
+            f.render_widget(input, chunks[1]);
+        }
+        Mode::RecursiveSearch => {
+            if let Some(search) = file_manager.get_recursive_search() {
+                let area = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+                    .split(chunks[1]);
+
+                let status = if search.is_running() {
+                    "поиск..."
+                } else {
+                    "готово"
+                };
+                let title = format!(
+                    "Поиск в {} ({}, просмотрено: {}) ",
+                    search.get_root().display(),
+                    status,
+                    search.get_scanned_count()
+                );
+                let input = Paragraph::new(search.get_query().as_ref())
+                    .block(Block::default().borders(Borders::ALL).title(title));
+
+                let items: Vec<ListItem> = search
+                    .get_results()
+                    .iter()
+                    .map(|path| {
+                        let name = relative_name(search.get_root(), path);
+                        let display_name = if path.is_dir() {
+                            format!("📁 {}", name)
+                        } else {
+                            format!("📄 {}", name)
+                        };
+                        ListItem::new(Span::raw(display_name))
+                    })
+                    .collect();
+
+                let mut results_state = ListState::default();
+                results_state.select(Some(*search.get_selected()));
+                let results = List::new(items)
+                    .block(Block::default().borders(Borders::ALL).title("Results"))
+                    .highlight_style(Style::default().bg(Color::Yellow))
+                    .highlight_symbol("|-> ");
+
+                f.render_widget(Clear, chunks[1]);
+                f.render_widget(input, area[0]);
+                f.render_stateful_widget(results, area[1], &mut results_state);
+            }
+        }
+        Mode::Jump => {
+            if let Some(jump) = file_manager.get_jump() {
+                let area = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+                    .split(chunks[1]);
+
+                let input = Paragraph::new(jump.get_query().as_ref()).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Перейти в частую папку "),
+                );
+
+                // слева — вес папки с учётом частоты и давности посещений
+                let items: Vec<ListItem> = jump
+                    .get_results()
+                    .iter()
+                    .map(|(path, score)| {
+                        ListItem::new(Spans::from(vec![
+                            Span::styled(
+                                format!("{:>7.1} ", score),
+                                Style::default().fg(Color::DarkGray),
+                            ),
+                            Span::raw(path.display().to_string()),
+                        ]))
+                    })
+                    .collect();
+
+                let mut results_state = ListState::default();
+                results_state.select(Some(*jump.get_selected()));
+                let results = List::new(items)
+                    .block(Block::default().borders(Borders::ALL).title("Папки"))
+                    .highlight_style(Style::default().bg(Color::Yellow))
+                    .highlight_symbol("|-> ");
+
+                f.render_widget(Clear, chunks[1]);
+                f.render_widget(input, area[0]);
+                f.render_stateful_widget(results, area[1], &mut results_state);
+            }
+        }
+        Mode::GoTo => {
+            if let Some(goto) = file_manager.get_goto() {
+                render_goto(f, goto, chunks[1]);
+            }
+        }
+        Mode::ContentSearch => {
+            if let Some(search) = file_manager.get_content_search() {
+                let area = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+                    .split(chunks[1]);
+
+                let kind = if search.is_regex() {
+                    "regex"
+                } else {
+                    "текст"
+                };
+                let status = if let Some(error) = search.get_error() {
+                    format!("ошибка: {}", error)
+                } else if search.is_running() {
+                    String::from("поиск...")
+                } else {
+                    format!("найдено: {}", search.get_results().len())
+                };
+                let title = format!(
+                    "Поиск по содержимому ({}) в {} ({}) ",
+                    kind,
+                    search.get_root().display(),
+                    status
+                );
+                let input = Paragraph::new(search.get_pattern().as_ref())
+                    .block(Block::default().borders(Borders::ALL).title(title));
+
+                let items: Vec<ListItem> = search
+                    .get_results()
+                    .iter()
+                    .map(|hit| {
+                        ListItem::new(Span::raw(format!(
+                            "{}:{}: {}",
+                            relative_name(search.get_root(), &hit.path),
+                            hit.line,
+                            hit.snippet
+                        )))
+                    })
+                    .collect();
+
+                let mut results_state = ListState::default();
+                results_state.select(Some(*search.get_selected()));
+                let results = List::new(items)
+                    .block(Block::default().borders(Borders::ALL).title("Matches"))
+                    .highlight_style(Style::default().bg(Color::Yellow))
+                    .highlight_symbol("|-> ");
+
+                f.render_widget(Clear, chunks[1]);
+                f.render_widget(input, area[0]);
+                f.render_stateful_widget(results, area[1], &mut results_state);
+            }
+        }
+        Mode::Trash => {
+            let items: Vec<ListItem> = file_manager
+                .get_trash_entries()
+                .iter()
+                .map(|entry| {
+                    ListItem::new(Span::raw(format!(
+                        "{}  {}",
+                        entry.deleted.replace('T', " "),
+                        entry.original.display()
+                    )))
+                })
+                .collect();
+            let title = "Корзина (Enter — восстановить, D — удалить навсегда)";
+            let mut trash_state = ListState::default();
+            trash_state.select(Some(*file_manager.get_trash_selected()));
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .highlight_style(Style::default().bg(Color::Yellow))
+                .highlight_symbol("|-> ");
+            f.render_widget(Clear, chunks[1]);
+            f.render_stateful_widget(list, chunks[1], &mut trash_state);
+        }
+        Mode::Conflict => {
+            let name = file_manager.get_conflict_name().unwrap_or_default();
+            let items: Vec<ListItem> = file_manager
+                .show_conflict()
+                .iter()
+                .enumerate()
+                .map(|(i, action)| {
+                    let style = if i == *file_manager.get_conflict_selected() {
+                        Style::default().fg(Color::LightBlue)
+                    } else {
+                        Style::default()
+                    };
+                    ListItem::new(Span::styled(*action, style))
+                })
+                .collect();
+            let title = format!("Уже существует: {}", name);
+            let menu = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .highlight_symbol("|-> ");
+            f.render_widget(Clear, chunks[1]);
+            f.render_widget(menu, chunks[1]);
+        }
+        Mode::Sort => {
+            let items: Vec<ListItem> = file_manager
+                .show_sort()
+                .into_iter()
+                .enumerate()
+                .map(|(i, label)| {
+                    let style = if i == *file_manager.get_sort_selected() {
+                        Style::default().fg(Color::LightBlue)
+                    } else {
+                        Style::default()
+                    };
+                    ListItem::new(Span::styled(label, style))
+                })
+                .collect();
+            let menu = List::new(items).block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Введите имя: "),
+                    .title("Сортировка (Enter — выбрать, Esc — закрыть)"),
             );
-            f.render_widget(input, chunks[1]);
+            f.render_widget(Clear, chunks[1]);
+            f.render_widget(menu, chunks[1]);
+        }
+        Mode::Columns => {
+            let view = file_manager.get_view();
+            let items: Vec<ListItem> = view
+                .get_columns()
+                .iter()
+                .enumerate()
+                .map(|(i, state)| {
+                    let style = if i == *view.get_selected() {
+                        Style::default().fg(Color::LightBlue)
+                    } else {
+                        Style::default()
+                    };
+                    let checked = if state.visible { "x" } else { " " };
+                    ListItem::new(Span::styled(
+                        format!("[{}] {} ({})", checked, state.column.title(), state.width),
+                        style,
+                    ))
+                })
+                .collect();
+            let menu = List::new(items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Колонки (Пробел — показать/скрыть, ←/→ — ширина, Esc — закрыть)"),
+            );
+            f.render_widget(Clear, chunks[1]);
+            f.render_widget(menu, chunks[1]);
+        }
+        Mode::BatchRename => {
+            if let Some(dialog) = file_manager.get_batch_rename() {
+                f.render_widget(Clear, chunks[1]);
+                render_batch_rename(f, dialog, chunks[1]);
+            }
         }
         Mode::Context => {
             let menu_items = file_manager.show_context();
@@ -115,4 +407,819 @@ pub fn render<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager) {
             f.render_widget(menu, chunks[1]);
         }
     }
+
+    if matches!(file_manager.get_mode(), Mode::History) {
+        render_history(f, file_manager);
+    }
+    if matches!(file_manager.get_mode(), Mode::Properties) {
+        render_properties(f, file_manager);
+    }
+    if matches!(file_manager.get_mode(), Mode::Bookmarks) {
+        render_bookmarks(f, file_manager);
+    }
+    if let Some(dialog) = file_manager.get_permissions() {
+        render_permissions(f, dialog);
+    }
+    if let Some(confirm) = file_manager.get_confirm() {
+        render_confirm(f, confirm);
+    }
+}
+
+// Подсвечивает совпадения в видимых строках файла, строку line (с единицы) выделяет фоном
+fn highlight_content<'a>(
+    content: &'a str,
+    line: usize,
+    regex: &Regex,
+    skip: usize,
+    height: usize,
+) -> Text<'a> {
+    let lines: Vec<Spans> = content
+        .lines()
+        .enumerate()
+        .skip(skip)
+        .take(height)
+        .map(|(i, text)| {
+            let base = if i + 1 == line {
+                Style::default().bg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            let mut spans = Vec::new();
+            let mut last = 0;
+            for found in regex.find_iter(text) {
+                spans.push(Span::styled(&text[last..found.start()], base));
+                spans.push(Span::styled(
+                    found.as_str(),
+                    Style::default().bg(Color::Yellow).fg(Color::Black),
+                ));
+                last = found.end();
+            }
+            spans.push(Span::styled(&text[last..], base));
+            Spans::from(spans)
+        })
+        .collect();
+    Text::from(lines)
+}
+
+// Модальное окно подтверждения поверх всего экрана
+fn render_confirm<B: Backend>(f: &mut Frame<B>, confirm: &Confirm) {
+    let height = confirm.get_lines().len() as u16 + 6;
+    let area = centered_rect(60, height, f.size());
+
+    let button = |label: &'static str, active: bool| {
+        if active {
+            Span::styled(label, Style::default().fg(Color::Black).bg(Color::Yellow))
+        } else {
+            Span::raw(label)
+        }
+    };
+    let mut lines: Vec<Spans> = confirm
+        .get_lines()
+        .iter()
+        .map(|line| Spans::from(line.as_str()))
+        .collect();
+    lines.push(Spans::default());
+    lines.push(Spans::from(vec![
+        button("[ Да (y) ]", confirm.is_yes()),
+        Span::raw("   "),
+        button("[ Нет (n) ]", !confirm.is_yes()),
+    ]));
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .title(confirm.get_title().as_str()),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// Последние операции: сверху отменённые (их можно повторить), ниже — выполненные
+fn render_history<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager) {
+    let journal = file_manager.get_journal();
+    let redo = journal.get_redo().iter().rev().take(HISTORY_SIZE).rev();
+    let undo = journal.get_undo().iter().rev().take(HISTORY_SIZE);
+
+    let mut lines: Vec<Spans> = redo
+        .map(|change| {
+            Spans::from(Span::styled(
+                format!("  {}", change.describe()),
+                Style::default().fg(Color::DarkGray),
+            ))
+        })
+        .collect();
+    lines.extend(undo.enumerate().map(|(i, change)| {
+        if i == 0 {
+            Spans::from(Span::styled(
+                format!("> {}", change.describe()),
+                Style::default().fg(Color::Yellow),
+            ))
+        } else {
+            Spans::from(format!("  {}", change.describe()))
+        }
+    }));
+    if lines.is_empty() {
+        lines.push(Spans::from("Операций пока не было"));
+    }
+
+    let area = centered_rect(60, lines.len() as u16 + 2, f.size());
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("История (u — отменить, Ctrl+R — повторить, Esc — закрыть)"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn render_properties<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager) {
+    let properties = file_manager.get_properties();
+    let label_width = properties
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Spans> = properties
+        .iter()
+        .map(|(label, value)| {
+            Spans::from(vec![
+                Span::styled(
+                    format!("{:width$}  ", label, width = label_width),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(value.as_str()),
+            ])
+        })
+        .collect();
+
+    let area = centered_rect(80, lines.len() as u16 + 2, f.size());
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Свойства (Esc — закрыть)"),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// Строка пути с ошибкой в заголовке и выпадающим списком вариантов дополнения
+fn render_goto<B: Backend>(f: &mut Frame<B>, goto: &GoTo, area: Rect) {
+    let rows = if goto.get_candidates().is_empty() {
+        0
+    } else {
+        goto.get_candidates().len() as u16 + 2
+    };
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Length(rows),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let title = match goto.get_error() {
+        Some(error) => Spans::from(vec![
+            Span::raw("Перейти к пути: "),
+            Span::styled(error.clone(), Style::default().fg(Color::Red)),
+        ]),
+        None => Spans::from("Перейти к пути (Tab — дополнить, ~ и $VAR раскрываются) "),
+    };
+    let input = Paragraph::new(format!("{}_", goto.get_input()))
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    let items: Vec<ListItem> = goto
+        .get_candidates()
+        .iter()
+        .map(|name| {
+            let style = if name.ends_with('/') {
+                Style::default().fg(Color::Blue)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Span::styled(name.clone(), style))
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(*goto.get_selected());
+    let candidates = List::new(items)
+        .block(Block::default().borders(Borders::ALL))
+        .highlight_style(Style::default().bg(Color::Yellow));
+
+    f.render_widget(Clear, area);
+    f.render_widget(input, layout[0]);
+    f.render_stateful_widget(candidates, layout[1], &mut state);
+}
+
+// Закладки: буква, имя и папка; отсутствующие папки выделены красным
+fn render_bookmarks<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager) {
+    let bookmarks = file_manager.get_bookmarks();
+    let name_width = bookmarks
+        .get_items()
+        .iter()
+        .map(|bookmark| bookmark.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<Spans> = bookmarks
+        .get_items()
+        .iter()
+        .enumerate()
+        .map(|(i, bookmark)| {
+            let mut style = if bookmark.path.is_dir() {
+                Style::default()
+            } else {
+                Style::default().fg(Color::Red)
+            };
+            if i == *bookmarks.get_selected() {
+                style = style.bg(Color::DarkGray);
+            }
+            Spans::from(vec![
+                Span::styled(format!("{}  ", bookmark.key), style.fg(Color::Yellow)),
+                Span::styled(
+                    format!(
+                        "{:width$}  {}",
+                        bookmark.name,
+                        bookmark.path.display(),
+                        width = name_width
+                    ),
+                    style,
+                ),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Spans::from(
+            "Закладок нет: m и буква запоминают текущую папку",
+        ));
+    }
+
+    let area = centered_rect(70, lines.len() as u16 + 2, f.size());
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Закладки (Enter — перейти, r — имя, d — удалить, Esc — закрыть)"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// Сетка rwx с особыми битами, восьмеричное поле, владелец и параметры обхода
+fn render_permissions<B: Backend>(f: &mut Frame<B>, dialog: &PermissionsDialog) {
+    let current = dialog.get_field();
+    let style_for = |field: Field| {
+        if field == current {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default()
+        }
+    };
+    let checkbox = |field: Field, checked: bool| {
+        Span::styled(if checked { "[x]" } else { "[ ]" }, style_for(field))
+    };
+    let text_field = |field: Field, value: &str| {
+        let cursor = if field == current { "_" } else { "" };
+        Span::styled(format!("{}{}", value, cursor), style_for(field))
+    };
+
+    let mut lines = vec![Spans::from("              r    w    x   s/t")];
+    for (row, name) in GRID.iter().zip(["Владелец ", "Группа   ", "Остальные"])
+    {
+        let mut spans = vec![Span::raw(format!("{}    ", name))];
+        for bit in row {
+            spans.push(checkbox(Field::Bit(*bit), dialog.is_set(*bit)));
+            spans.push(Span::raw("  "));
+        }
+        lines.push(Spans::from(spans));
+    }
+    lines.push(Spans::from("s/t — setuid, setgid и sticky"));
+    lines.push(Spans::default());
+    lines.push(Spans::from(vec![
+        Span::raw("Восьмерично: "),
+        text_field(Field::Octal, dialog.get_octal()),
+    ]));
+    lines.push(Spans::from(vec![
+        Span::raw("Владелец:    "),
+        text_field(Field::Owner, dialog.get_owner()),
+    ]));
+    lines.push(Spans::from(vec![
+        Span::raw("Группа:      "),
+        text_field(Field::Group, dialog.get_group()),
+    ]));
+    lines.push(Spans::default());
+    lines.push(Spans::from(vec![
+        checkbox(Field::Recursive, dialog.is_recursive()),
+        Span::raw(" Рекурсивно"),
+    ]));
+    lines.push(Spans::from(vec![
+        checkbox(Field::ExecDirsOnly, dialog.is_exec_dirs_only()),
+        Span::raw(" x только папкам и уже исполняемым файлам"),
+    ]));
+
+    let title = format!(
+        "Права: {} эл. (Tab — поле, Пробел — флажок, Enter — применить)",
+        dialog.get_targets().len()
+    );
+    let area = centered_rect(60, lines.len() as u16 + 2, f.size());
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// Поля шаблона сверху, ниже таблица «было → станет»; совпадающие имена выделены красным
+fn render_batch_rename<B: Backend>(f: &mut Frame<B>, dialog: &BatchRenameDialog, area: Rect) {
+    let area = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(6), Constraint::Min(0)].as_ref())
+        .split(area);
+
+    let current = dialog.get_field();
+    let text_field = |field: batch_rename::Field, label: &'static str, value: &str| {
+        let (style, cursor) = if field == current {
+            (Style::default().fg(Color::Black).bg(Color::Yellow), "_")
+        } else {
+            (Style::default(), "")
+        };
+        Spans::from(vec![
+            Span::raw(label),
+            Span::styled(format!("{}{}", value, cursor), style),
+        ])
+    };
+    let lines = vec![
+        text_field(
+            batch_rename::Field::Find,
+            "Найти (regex):  ",
+            dialog.get_find(),
+        ),
+        text_field(
+            batch_rename::Field::Replace,
+            "Заменить на:    ",
+            dialog.get_replace(),
+        ),
+        text_field(
+            batch_rename::Field::Case,
+            "Регистр:        ",
+            dialog.get_case().label(),
+        ),
+        text_field(
+            batch_rename::Field::Extension,
+            "Расширение:     ",
+            dialog.get_extension(),
+        ),
+    ];
+    let title = "Переименование по шаблону ($1 — группа, {n:03} — счётчик, {name}, {ext}; Tab — поле, Пробел — регистр, Enter — применить)";
+    let fields = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(fields, area[0]);
+
+    let rows: Vec<Row> = dialog
+        .get_preview()
+        .iter()
+        .map(|row| {
+            let name = |path: &Path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            };
+            let style = if row.collision {
+                Style::default().fg(Color::Red)
+            } else if row.from == row.to {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            Row::new(vec![
+                Cell::from(name(&row.from)),
+                Cell::from("→"),
+                Cell::from(name(&row.to)),
+            ])
+            .style(style)
+        })
+        .collect();
+    let status = if let Some(error) = dialog.get_error() {
+        format!("ошибка: {}", error)
+    } else if dialog.get_collision_count() > 0 {
+        format!("совпадающих имён: {}", dialog.get_collision_count())
+    } else {
+        format!("элементов: {}", dialog.get_preview().len())
+    };
+    let table = Table::new(rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Предпросмотр ({})", status)),
+        )
+        .widths(&[
+            Constraint::Percentage(48),
+            Constraint::Length(2),
+            Constraint::Percentage(48),
+        ]);
+    f.render_widget(table, area[1]);
+}
+
+fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+// Полоса вкладок: номер (для Alt+N) и папка активной панели
+fn render_tabs<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager, area: Rect) {
+    let titles: Vec<Spans> = file_manager
+        .get_tabs()
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| Spans::from(format!("{}: {}", i + 1, tab.title())))
+        .collect();
+    let tabs = Tabs::new(titles)
+        .select(file_manager.get_tab_index())
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow));
+    f.render_widget(tabs, area);
+}
+
+// Заголовок с состоянием панели и её список; активная панель выделена рамкой
+fn render_panel<B: Backend>(
+    f: &mut Frame<B>,
+    file_manager: &FileManager,
+    index: usize,
+    area: Rect,
+) {
+    let tab = file_manager.get_tab();
+    let panel = &tab.get_panels()[index];
+    let focused = index == tab.get_active();
+
+    let mut title = panel.get_current_dir().display().to_string();
+    if panel.is_filter_active() {
+        title.push_str(&format!(" (фильтр: {})", panel.get_search_buffer()));
+    }
+    if panel.get_sort() != Sort::default() {
+        title.push_str(&format!(" [{}]", panel.get_sort().label()));
+    }
+    let visibility = file_manager.get_visibility();
+    if !visibility.is_showing_hidden() {
+        title.push_str(" [без скрытых]");
+    }
+    if !visibility.get_patterns().is_empty() {
+        title.push_str(&format!(" [шаблоны: {}]", visibility.get_patterns().len()));
+    }
+    if visibility.is_using_ignore_files() {
+        title.push_str(" [.gitignore]");
+    }
+    if panel.get_hidden_count() > 0 {
+        title.push_str(&format!(" (скрыто: {})", panel.get_hidden_count()));
+    }
+    if panel.is_visual() {
+        title.push_str(" [выделение]");
+    }
+    if focused {
+        if let Some(clipboard) = file_manager.get_clipboard() {
+            let kind = if clipboard.is_cut() {
+                "вырезано"
+            } else {
+                "в буфере"
+            };
+            title.push_str(&format!(" ({}: {})", kind, clipboard.len()));
+        }
+    }
+    if panel.get_marked_count() > 0 {
+        title.push_str(&format!(" (отмечено: {})", panel.get_marked_count()));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        });
+    let highlight = if focused {
+        Style::default().bg(Color::Yellow)
+    } else {
+        Style::default().bg(Color::DarkGray)
+    };
+
+    f.render_widget(Clear, area);
+    if file_manager.get_view().is_detailed() {
+        render_table(f, file_manager, panel, area, block, highlight);
+    } else {
+        let mut state = ListState::default();
+        state.select(Some(*panel.get_selected()));
+
+        let items: Vec<ListItem> = panel
+            .get_files()
+            .iter()
+            .enumerate()
+            .map(|(i, path)| ListItem::new(name_spans(panel, i, path)))
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(highlight)
+            .highlight_symbol("|-> ");
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+// Отметка, значок и имя файла с подсвеченными совпадениями фильтра
+fn name_spans<'a>(panel: &Panel, index: usize, path: &Path) -> Spans<'a> {
+    let marked = panel.is_marked(index);
+    let kind = panel.get_kind(path);
+    let link = matches!(kind, Kind::Link | Kind::BrokenLink);
+    let broken = kind == Kind::BrokenLink;
+    let style = if marked {
+        Style::default().fg(Color::LightGreen)
+    } else if broken {
+        Style::default()
+            .fg(Color::Red)
+            .add_modifier(Modifier::CROSSED_OUT)
+    } else if link {
+        Style::default().fg(Color::Cyan)
+    } else if index == *panel.get_selected() {
+        Style::default().fg(Color::White)
+    } else {
+        Style::default()
+    };
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let icon = if link {
+        "🔗 "
+    } else if kind == Kind::Dir {
+        "📁 "
+    } else {
+        "📄 "
+    };
+    let indices = panel.get_filter_indices(&name);
+
+    let marker = if marked { "* " } else { "  " };
+    let mut spans = vec![Span::styled(marker, style), Span::styled(icon, style)];
+    spans.extend(name.chars().enumerate().map(|(j, c)| {
+        if indices.contains(&j) {
+            Span::styled(c.to_string(), style.fg(Color::LightRed))
+        } else {
+            Span::styled(c.to_string(), style)
+        }
+    }));
+    Spans::from(spans)
+}
+
+// Подробный список; метаданные читаются только для видимых строк
+fn render_table<B: Backend>(
+    f: &mut Frame<B>,
+    file_manager: &FileManager,
+    panel: &Panel,
+    area: Rect,
+    block: Block,
+    highlight: Style,
+) {
+    let columns: Vec<&ColumnState> = file_manager
+        .get_view()
+        .get_columns()
+        .iter()
+        .filter(|state| state.visible)
+        .collect();
+
+    // как и List, держим выделенную строку в пределах окна
+    let height = area.height.saturating_sub(3).max(1) as usize;
+    let selected = *panel.get_selected();
+    let offset = (selected + 1).saturating_sub(height);
+
+    let rows: Vec<Row> = panel
+        .get_files()
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, path)| {
+            let metadata = fs::symlink_metadata(path).ok();
+            let mut cells = vec![Cell::from(name_spans(panel, i, path))];
+            cells.extend(columns.iter().map(|state| {
+                let text = metadata
+                    .as_ref()
+                    .map(|metadata| column_text(state.column, path, metadata))
+                    .unwrap_or_default();
+                Cell::from(text)
+            }));
+            Row::new(cells)
+        })
+        .collect();
+
+    let mut header = vec![Cell::from("Имя")];
+    header.extend(columns.iter().map(|state| Cell::from(state.column.title())));
+    let mut widths = vec![Constraint::Min(10)];
+    widths.extend(columns.iter().map(|state| Constraint::Length(state.width)));
+
+    let mut state = TableState::default();
+    state.select(Some(selected - offset));
+    let table = Table::new(rows)
+        .header(Row::new(header).style(Style::default().fg(Color::Yellow)))
+        .block(block)
+        .widths(&widths)
+        .column_spacing(1)
+        .highlight_style(highlight)
+        .highlight_symbol("|-> ");
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+fn column_text(column: Column, path: &Path, metadata: &Metadata) -> String {
+    match column {
+        Column::Permissions => format_permissions(metadata.mode()),
+        Column::Owner => format!(
+            "{}:{}",
+            owners::user_name(metadata.uid()),
+            owners::group_name(metadata.gid())
+        ),
+        Column::Size if metadata.is_dir() => String::from("<DIR>"),
+        Column::Size => format_size(metadata.len()),
+        Column::Modified => format_time(metadata),
+        Column::Target => fs::read_link(path)
+            .map(|target| format!("→ {}", target.display()))
+            .unwrap_or_default(),
+    }
+}
+
+fn format_time(metadata: &Metadata) -> String {
+    metadata
+        .modified()
+        .map(|time| {
+            DateTime::<Local>::from(time)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+// Строка состояния: путь, число элементов и сведения о выбранном файле или сообщение
+fn render_status<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager, area: Rect) {
+    let panel = file_manager.get_panel();
+    let count = panel.get_files().len();
+    let total = panel.get_total_count();
+    let count = if count == total {
+        format!("{} эл.", total)
+    } else {
+        format!("{} из {} эл.", count, total)
+    };
+    let mut spans = vec![
+        Span::styled(
+            panel.get_current_dir().display().to_string(),
+            Style::default().fg(Color::LightBlue),
+        ),
+        Span::raw(format!("  {}  ", count)),
+    ];
+
+    if let Some(message) = file_manager.get_message() {
+        let color = match message.get_level() {
+            Level::Info => Color::Green,
+            Level::Warn => Color::Yellow,
+            Level::Error => Color::Red,
+        };
+        spans.push(Span::styled(
+            message.get_text().as_str(),
+            Style::default().fg(color),
+        ));
+    } else if let Some(metadata) = panel
+        .get_selected_path()
+        .and_then(|path| fs::symlink_metadata(path).ok())
+    {
+        let size = if metadata.is_dir() {
+            String::from("<DIR>")
+        } else {
+            format_size(metadata.len())
+        };
+        let modified = format_time(&metadata);
+        spans.push(Span::styled(
+            format!(
+                "{}  {}  {}",
+                format_permissions(metadata.permissions().mode()),
+                size,
+                modified
+            ),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    f.render_widget(Paragraph::new(Spans::from(spans)), area);
+}
+
+// Права в виде drwxr-xr-x, с учётом setuid/setgid/sticky
+pub fn format_permissions(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '-',
+    };
+    let mut result = String::from(kind);
+    for (shift, special, special_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        result.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        result.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        result.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => special_char,
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    result
+}
+
+fn render_jobs<B: Backend>(f: &mut Frame<B>, file_manager: &FileManager, area: Rect) {
+    let jobs = file_manager.get_jobs();
+    let focused = matches!(file_manager.get_mode(), Mode::Jobs);
+    let title = if focused {
+        "Jobs (c — отменить, Esc — назад)"
+    } else {
+        "Jobs (J — управление)"
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let selected = *jobs.get_selected();
+    let first = selected.saturating_sub(MAX_VISIBLE_JOBS - 1);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(1); MAX_VISIBLE_JOBS])
+        .split(inner);
+
+    for (row, (i, job)) in rows
+        .iter()
+        .zip(jobs.get_jobs().iter().enumerate().skip(first))
+    {
+        let color = match job.get_state() {
+            JobState::Running => Color::Yellow,
+            JobState::Done => Color::Green,
+            JobState::Failed(_) => Color::Red,
+            JobState::Cancelled => Color::DarkGray,
+        };
+        let mut style = Style::default().fg(color).bg(Color::Black);
+        if focused && i == selected {
+            style = style.bg(Color::DarkGray);
+        }
+        let gauge = Gauge::default()
+            .gauge_style(style)
+            .ratio(job.ratio())
+            .label(job_label(job));
+        f.render_widget(gauge, *row);
+    }
+}
+
+fn job_label(job: &Job) -> String {
+    let (files_done, files_total) = job.get_files();
+    let (bytes_done, bytes_total) = job.get_bytes();
+    let status = match job.get_state() {
+        JobState::Running if !job.is_scanned() => String::from("подсчёт..."),
+        JobState::Running => match job.eta() {
+            Some(eta) => format!("осталось {}", format_duration(eta)),
+            None => String::new(),
+        },
+        JobState::Done => String::from("готово"),
+        JobState::Failed(error) => format!("ошибка: {}", error),
+        JobState::Cancelled => String::from("отменено"),
+    };
+    format!(
+        "{}: {}/{} файлов, {} / {} {}",
+        job.get_title(),
+        files_done,
+        files_total,
+        format_size(bytes_done),
+        format_size(bytes_total),
+        status
+    )
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
 }
@@ -0,0 +1,107 @@
+// Границы ширины колонки подробного списка
+const MIN_WIDTH: u16 = 4;
+const MAX_WIDTH: u16 = 60;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Column {
+    Permissions,
+    Owner,
+    Size,
+    Modified,
+    Target,
+}
+
+impl Column {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Permissions => "Права",
+            Column::Owner => "Владелец",
+            Column::Size => "Размер",
+            Column::Modified => "Изменён",
+            Column::Target => "Ссылка на",
+        }
+    }
+}
+
+pub struct ColumnState {
+    pub column: Column,
+    pub visible: bool,
+    pub width: u16,
+}
+
+// Компактный или подробный список и настройки колонок подробного
+pub struct View {
+    detailed: bool,
+    columns: Vec<ColumnState>,
+    selected: usize,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        let column = |column, width| ColumnState {
+            column,
+            visible: true,
+            width,
+        };
+        Self {
+            detailed: false,
+            columns: vec![
+                column(Column::Permissions, 10),
+                column(Column::Owner, 16),
+                column(Column::Size, 9),
+                column(Column::Modified, 16),
+                column(Column::Target, 24),
+            ],
+            selected: 0,
+        }
+    }
+}
+
+impl View {
+    pub fn is_detailed(&self) -> bool {
+        self.detailed
+    }
+
+    pub fn get_columns(&self) -> &Vec<ColumnState> {
+        &self.columns
+    }
+
+    pub fn get_selected(&self) -> &usize {
+        &self.selected
+    }
+
+    pub fn toggle_detailed(&mut self) {
+        self.detailed = !self.detailed;
+    }
+
+    pub fn up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        } else {
+            self.selected = self.columns.len() - 1;
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.columns.len() {
+            self.selected += 1;
+        } else {
+            self.selected = 0;
+        }
+    }
+
+    pub fn toggle_selected(&mut self) {
+        if let Some(state) = self.columns.get_mut(self.selected) {
+            state.visible = !state.visible;
+        }
+    }
+
+    pub fn resize_selected(&mut self, delta: i16) {
+        if let Some(state) = self.columns.get_mut(self.selected) {
+            state.width = state
+                .width
+                .saturating_add_signed(delta)
+                .clamp(MIN_WIDTH, MAX_WIDTH);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::config;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::io;
+use std::path::Path;
+
+// Файл с шаблонами скрытия, по одному на строку
+const PATTERNS_FILE: &str = "ignore";
+// Порядок важен: правила .ignore перекрывают .gitignore
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+// Какие элементы папки попадают в список
+pub struct Visibility {
+    show_hidden: bool,
+    use_ignore_files: bool,
+    patterns: Vec<String>,
+    names: GlobSet,
+    dirs: GlobSet,
+    // правила для текущей папки, от ближайшей к корню репозитория
+    ignore_files: Vec<Gitignore>,
+}
+
+impl Visibility {
+    pub fn load() -> Self {
+        // некорректные шаблоны из файла пропускаются
+        let patterns: Vec<String> = config::config_path(PATTERNS_FILE)
+            .map(|path| config::read_lines(&path))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|pattern| compile(std::slice::from_ref(pattern)).is_ok())
+            .collect();
+        let (names, dirs) = compile(&patterns).unwrap_or_default();
+
+        Self {
+            show_hidden: true,
+            use_ignore_files: false,
+            patterns,
+            names,
+            dirs,
+            ignore_files: Vec::new(),
+        }
+    }
+
+    // Getters
+    pub fn is_showing_hidden(&self) -> bool {
+        self.show_hidden
+    }
+
+    pub fn is_using_ignore_files(&self) -> bool {
+        self.use_ignore_files
+    }
+
+    pub fn get_patterns(&self) -> &Vec<String> {
+        &self.patterns
+    }
+
+    pub fn toggle_hidden(&mut self) {
+        self.show_hidden = !self.show_hidden;
+    }
+
+    pub fn toggle_ignore_files(&mut self) {
+        self.use_ignore_files = !self.use_ignore_files;
+    }
+
+    // Шаблоны разделяются пробелами или запятыми и сохраняются в конфиг
+    pub fn set_patterns(&mut self, input: &str) -> io::Result<()> {
+        let patterns: Vec<String> = input
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|pattern| !pattern.is_empty())
+            .map(String::from)
+            .collect();
+        let (names, dirs) = compile(&patterns)?;
+
+        if let Some(path) = config::config_path(PATTERNS_FILE) {
+            config::write_lines(&path, &patterns)?;
+        }
+        self.patterns = patterns;
+        self.names = names;
+        self.dirs = dirs;
+        Ok(())
+    }
+
+    // Перечитывает .gitignore/.ignore от папки вверх до корня репозитория
+    pub fn prepare(&mut self, dir: &Path) {
+        self.ignore_files.clear();
+        if !self.use_ignore_files {
+            return;
+        }
+        for ancestor in dir.ancestors() {
+            let mut builder = GitignoreBuilder::new(ancestor);
+            let mut found = false;
+            for name in IGNORE_FILES {
+                let path = ancestor.join(name);
+                if path.is_file() {
+                    // битые строки пропускаются, остальные правила применяются
+                    builder.add(path);
+                    found = true;
+                }
+            }
+            if found {
+                if let Ok(gitignore) = builder.build() {
+                    self.ignore_files.push(gitignore);
+                }
+            }
+            if ancestor.join(".git").exists() {
+                break;
+            }
+        }
+    }
+
+    pub fn is_visible(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(name) = path.file_name() else {
+            return true;
+        };
+        if !self.show_hidden && name.to_string_lossy().starts_with('.') {
+            return false;
+        }
+        if self.names.is_match(name) || (is_dir && self.dirs.is_match(name)) {
+            return false;
+        }
+        for gitignore in &self.ignore_files {
+            match gitignore.matched(path, is_dir) {
+                Match::Ignore(_) => return false,
+                Match::Whitelist(_) => return true,
+                Match::None => {}
+            }
+        }
+        true
+    }
+}
+
+// Шаблон с / на конце ("target/") скрывает только папки
+fn compile(patterns: &[String]) -> io::Result<(GlobSet, GlobSet)> {
+    let mut names = GlobSetBuilder::new();
+    let mut dirs = GlobSetBuilder::new();
+    for pattern in patterns {
+        let (builder, glob) = match pattern.strip_suffix('/') {
+            Some(dir) => (&mut dirs, dir),
+            None => (&mut names, pattern.as_str()),
+        };
+        builder.add(Glob::new(glob).map_err(invalid_input)?);
+    }
+    Ok((
+        names.build().map_err(invalid_input)?,
+        dirs.build().map_err(invalid_input)?,
+    ))
+}
+
+fn invalid_input(err: globset::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}